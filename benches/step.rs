@@ -0,0 +1,72 @@
+// cargo bench
+//
+// allocating (gtf, one new Lattice per generation) versus double buffered
+// (step_into via Stepper) stepping, on the lattices search.sh uses:
+// radix 8, neighborhood 7, 400 wide, 400 generations
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use ca1d::{Border, CAEvalType, Cell, Lattice, CA};
+
+const RADIX: u32 = 8;
+const NABOR: u32 = 7;
+const WIDTH: usize = 400;
+const GENS: usize = 400;
+const RUNS: u32 = 20;
+
+fn time<F: FnMut()>(mut f: F) -> Duration {
+    // warm up
+    f();
+
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        f();
+    }
+    start.elapsed() / RUNS
+}
+
+fn report(name: &str, d: Duration) {
+    let cells = (WIDTH * GENS) as f64 / d.as_secs_f64();
+    println!("{:>12}: {:>10.3?} per run, {:>8.2} Mcells/s", name, d, cells / 1e6);
+}
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(30);
+    let start: Lattice = (0..WIDTH).map(|_| rng.random_range(0..RADIX as Cell)).collect();
+
+    for border in [Border::Ring, Border::Fixed] {
+        let ca = CA::new(
+            NABOR,
+            RADIX,
+            CAEvalType::new("3973227526303").unwrap(),
+            border,
+        );
+
+        println!("{:?}", border);
+
+        let alloc = time(|| {
+            let mut config = start.clone();
+            for _ in 0..GENS {
+                config = ca.gtf(black_box(&config));
+            }
+            black_box(config);
+        });
+        report("gtf", alloc);
+
+        let stepped = time(|| {
+            let mut stepper = ca.stepper(start.clone());
+            for _ in 0..GENS {
+                stepper.step();
+                black_box(stepper.current());
+            }
+            black_box(stepper.into_current());
+        });
+        report("step_into", stepped);
+
+        println!("{:>12}: {:.2}x", "speedup", alloc.as_secs_f64() / stepped.as_secs_f64());
+    }
+}
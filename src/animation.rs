@@ -0,0 +1,131 @@
+use std::borrow::Cow;
+use std::io;
+use std::io::Write;
+
+use gif::{Encoder, EncodingError, Frame, Repeat};
+
+use crate::{CAWriter, Cell, Lattice, Raster, WriterOpts, CELL0};
+
+fn gif_err(e: EncodingError) -> io::Error {
+    match e {
+        EncodingError::Io(e) => e,
+        e => io::Error::other(e),
+    }
+}
+
+// Animated GIF, one frame per generation.
+// Each frame shows the last opts.window generations with the newest at the
+// bottom (or the right when transposed), so a window > 1 scrolls the
+// automata through the image as it runs
+pub struct GIFWriter<W: Write> {
+    // taken by finish(), into_inner() writes the trailer
+    encoder: Option<Encoder<W>>,
+    raster: Raster,
+    width: usize,
+    window: usize,
+    transpose: bool,
+    delay: u16,
+    frame_size: (u16, u16),
+    // last window generations, oldest first
+    rows: Vec<Cell>,
+    // rows turned on their side when transposed
+    cols: Vec<Cell>,
+    pixels: Vec<u8>,
+}
+
+impl<W: Write> GIFWriter<W> {
+    pub fn new(w: W, radix: u32, width: usize, opts: &WriterOpts) -> io::Result<Self> {
+        let raster = Raster::new(radix, opts)?;
+        let window = usize::max(opts.window, 1);
+
+        let (cw, ch) = if opts.transpose { (window, width) } else { (width, window) };
+        let fw = u16::try_from(cw * raster.scale);
+        let fh = u16::try_from(ch * raster.scale);
+
+        let (fw, fh) = match (fw, fh) {
+            (Ok(w), Ok(h)) => (w, h),
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, "GIF frames are limited to 65535 pixels a side")),
+        };
+
+        let mut palette = Vec::new();
+        for (r, g, b) in raster.palette() {
+            palette.extend_from_slice(&[r, g, b]);
+        }
+
+        let mut encoder = Encoder::new(w, fw, fh, &palette).map_err(gif_err)?;
+        let repeat = match opts.loops {
+            0 => Repeat::Infinite,
+            // GIFs count repeats after the first play
+            n => Repeat::Finite(n - 1),
+        };
+        encoder.set_repeat(repeat).map_err(gif_err)?;
+
+        Ok(Self {
+            encoder: Some(encoder),
+            raster,
+            width,
+            window,
+            transpose: opts.transpose,
+            delay: opts.delay,
+            frame_size: (fw, fh),
+            rows: vec![CELL0; width * window],
+            cols: vec![CELL0; width * window],
+            pixels: vec![0; fw as usize * fh as usize],
+        })
+    }
+
+    fn render(&mut self) {
+        let (cells, w) = if self.transpose {
+            for (y, row) in self.rows.chunks(self.width).enumerate() {
+                for (x, c) in row.iter().enumerate() {
+                    self.cols[x * self.window + y] = *c;
+                }
+            }
+            (&self.cols, self.window)
+        } else {
+            (&self.rows, self.width)
+        };
+
+        let scale = self.raster.scale;
+        let mut lines = self.pixels.chunks_mut(w * scale);
+
+        for row in cells.chunks(w) {
+            for i in 0..scale {
+                self.raster.row(row, i, lines.next().unwrap());
+            }
+        }
+    }
+}
+
+impl<W: Write> CAWriter for GIFWriter<W> {
+    fn write_line(&mut self, v: &Lattice) -> io::Result<()> {
+        // scroll everything up a row, new generation goes on the bottom
+        self.rows.copy_within(self.width.., 0);
+        let last = self.rows.len() - self.width;
+        self.rows[last..].copy_from_slice(v);
+
+        self.render();
+
+        let encoder = match &mut self.encoder {
+            Some(e) => e,
+            None => return Ok(()),
+        };
+
+        let frame = Frame {
+            delay: self.delay,
+            width: self.frame_size.0,
+            height: self.frame_size.1,
+            buffer: Cow::Borrowed(&self.pixels),
+            ..Frame::default()
+        };
+
+        encoder.write_frame(&frame).map_err(gif_err)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if let Some(encoder) = self.encoder.take() {
+            encoder.into_inner().map_err(gif_err)?.flush()?;
+        }
+        Ok(())
+    }
+}
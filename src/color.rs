@@ -0,0 +1,164 @@
+use std::env;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use termcolor::{Ansi, Color, ColorSpec, WriteColor};
+
+use crate::Rgb;
+
+// --color
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum ColorWhen {
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorWhen {
+    type Err = &'static str;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "auto" => Ok(ColorWhen::Auto),
+            "always" => Ok(ColorWhen::Always),
+            "never" => Ok(ColorWhen::Never),
+            _ => Err("invalid color choice"),
+        }
+    }
+}
+
+// what a terminal can show, ordered from least to most
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+pub enum ColorDepth {
+    NoColor,
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+impl ColorWhen {
+    // tty is whether the output is going to a terminal
+    pub fn depth(self, tty: bool) -> ColorDepth {
+        match self {
+            ColorWhen::Never => ColorDepth::NoColor,
+            ColorWhen::Always => ColorDepth::from_env().unwrap_or(ColorDepth::TrueColor),
+            ColorWhen::Auto => {
+                let no_color = env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+                let dumb = env::var("TERM").is_ok_and(|t| t == "dumb");
+
+                if no_color || dumb || !tty {
+                    return ColorDepth::NoColor;
+                }
+
+                ColorDepth::from_env().unwrap_or(ColorDepth::Ansi16)
+            }
+        }
+    }
+}
+
+// xterm's values for the 16 standard colors
+const ANSI16: [Rgb; 16] = [
+    (0, 0, 0), (205, 0, 0), (0, 205, 0), (205, 205, 0),
+    (0, 0, 238), (205, 0, 205), (0, 205, 205), (229, 229, 229),
+    (127, 127, 127), (255, 0, 0), (0, 255, 0), (255, 255, 0),
+    (92, 92, 255), (255, 0, 255), (0, 255, 255), (255, 255, 255),
+];
+
+// levels of each channel in the 6x6x6 cube of the 256 color set
+const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn dist((r0, g0, b0): Rgb, (r1, g1, b1): Rgb) -> u32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+    d(r0, r1) + d(g0, g1) + d(b0, b1)
+}
+
+fn nearest(colors: impl Iterator<Item = (u8, Rgb)>, rgb: Rgb) -> u8 {
+    colors.min_by_key(|(_, c)| dist(*c, rgb)).unwrap().0
+}
+
+// closest of the cube (16..232) and the grey ramp (232..256)
+fn ansi256(rgb: Rgb) -> u8 {
+    let level = |x: u8| nearest(CUBE.iter().enumerate().map(|(i, l)| (i as u8, (*l, 0, 0))), (x, 0, 0));
+    let (r, g, b) = (level(rgb.0), level(rgb.1), level(rgb.2));
+    let cube = 16 + 36 * r + 6 * g + b;
+    let cube_rgb = (CUBE[r as usize], CUBE[g as usize], CUBE[b as usize]);
+
+    let grey = nearest((0..24).map(|i| (232 + i, (8 + 10 * i, 8 + 10 * i, 8 + 10 * i))), rgb);
+    let grey_level = 8 + 10 * (grey - 232);
+
+    if dist((grey_level, grey_level, grey_level), rgb) < dist(cube_rgb, rgb) {
+        grey
+    } else {
+        cube
+    }
+}
+
+// the low 8 are the plain SGR colors, the bright ones are Ansi256(8..16) for
+// set_colors to write as bright SGR codes
+fn ansi16(rgb: Rgb) -> Color {
+    match nearest(ANSI16.iter().enumerate().map(|(i, c)| (i as u8, *c)), rgb) {
+        0 => Color::Black,
+        1 => Color::Red,
+        2 => Color::Green,
+        3 => Color::Yellow,
+        4 => Color::Blue,
+        5 => Color::Magenta,
+        6 => Color::Cyan,
+        7 => Color::White,
+        n => Color::Ansi256(n),
+    }
+}
+
+impl ColorDepth {
+    // from COLORTERM and TERM, None if they don't say
+    fn from_env() -> Option<ColorDepth> {
+        if let Ok(ct) = env::var("COLORTERM") {
+            if ct == "truecolor" || ct == "24bit" {
+                return Some(ColorDepth::TrueColor);
+            }
+        }
+
+        match env::var("TERM") {
+            Ok(t) if t.contains("256color") => Some(ColorDepth::Ansi256),
+            Ok(t) if t.contains("direct") => Some(ColorDepth::TrueColor),
+            Ok(t) if !t.is_empty() && t != "dumb" => Some(ColorDepth::Ansi16),
+            _ => None,
+        }
+    }
+
+    // nearest color this depth can show, None for NoColor
+    pub fn quantize(self, rgb: Rgb) -> Option<Color> {
+        match self {
+            ColorDepth::NoColor => None,
+            ColorDepth::Ansi16 => Some(ansi16(rgb)),
+            ColorDepth::Ansi256 => Some(Color::Ansi256(ansi256(rgb))),
+            ColorDepth::TrueColor => Some(Color::Rgb(rgb.0, rgb.1, rgb.2)),
+        }
+    }
+}
+
+// the 16 color bright colors, 0..8
+fn bright(c: Option<Color>) -> Option<u8> {
+    match c {
+        Some(Color::Ansi256(n)) if (8..16).contains(&n) => Some(n - 8),
+        _ => None,
+    }
+}
+
+// termcolor writes the bright colors (even its intense ones) as 256 color
+// codes, which a 16 color terminal can't show. Those go out as SGR 90-97 and
+// 100-107 instead, the rest through termcolor
+pub(crate) fn set_colors<W: Write>(w: &mut Ansi<W>, fg: Option<Color>, bg: Option<Color>) -> io::Result<()> {
+    let mut spec = ColorSpec::new();
+    spec.set_fg(fg.filter(|_| bright(fg).is_none()));
+    spec.set_bg(bg.filter(|_| bright(bg).is_none()));
+    w.set_color(&spec)?;
+
+    if let Some(n) = bright(fg) {
+        write!(w, "\x1b[{}m", 90 + n)?;
+    }
+    if let Some(n) = bright(bg) {
+        write!(w, "\x1b[{}m", 100 + n)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,267 @@
+// commands other than running an automaton, picked by the first argument
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+use clap::{CommandFactory, Parser};
+use serde::{Deserialize, Serialize};
+
+use ca1d::{from_digit, Border, Cell, CAEvalType, ColorDepth, ColorWhen, Lattice, Output, OutputSpec, PaletteSpec, RuleIcon, WriterOpts, CA};
+
+use crate::{cmd_line, term_width, Opts};
+
+/// Draw a rule's table, each neighborhood above the cell it becomes
+/// (or each sum, for codes)
+#[derive(Parser, Debug)]
+#[clap(name = "ca1d icon")]
+struct IconOpts {
+    /// number of symbols (1, 36]
+    radix: u32,
+
+    /// neighbor size, centered (must be odd)
+    nabor_size: u32,
+
+    /// rule or code number, as for ca1d
+    rule_number: CAEvalType,
+
+    /// UnicodeAnsi, PNG or SVG, or TYPE:PATH to write to a file
+    #[clap(short, long, default_value("UnicodeAnsi"))]
+    output: OutputSpec,
+
+    /// entries per row, 0 fits them to the terminal (8 a row for images)
+    #[clap(long, default_value("0"))]
+    columns: usize,
+
+    /// PNG: pixels per cell, at least 2 for the grid lines
+    #[clap(long, default_value("8"))]
+    scale: usize,
+
+    /// colors, as for ca1d
+    #[clap(long, default_value("classic"))]
+    palette: PaletteSpec,
+
+    /// terminal colors: auto, always or never
+    #[clap(long, default_value("auto"))]
+    color: ColorWhen,
+}
+
+pub fn icon(args: &[String]) -> Result<(), String> {
+    let opts = IconOpts::parse_from(args);
+
+    if opts.radix < 2 || opts.radix > 36 {
+        return Err(format!("don't understand CA with {} states", opts.radix));
+    }
+    if opts.nabor_size % 2 == 0 {
+        return Err("neighborhood must be an odd number".to_string());
+    }
+    if !matches!(opts.output.output, Output::UnicodeAnsi | Output::PNG | Output::SVG) {
+        return Err("icons can be UnicodeAnsi, PNG or SVG".to_string());
+    }
+    if opts.scale < 2 {
+        return Err("scale must be at least 2".to_string());
+    }
+
+    let mut colors = opts.palette.colors(opts.radix)?;
+    let ca = CA::new(opts.nabor_size, opts.radix, opts.rule_number.clone(), Border::Ring);
+    let icon = RuleIcon::new(&ca)?;
+
+    let columns = match (opts.columns, opts.output.output) {
+        (0, Output::UnicodeAnsi) => icon.columns(term_width(0)),
+        (0, _) => 8,
+        (n, _) => n,
+    };
+
+    if opts.output.output == Output::SVG {
+        let svg = icon.svg(&colors, columns);
+        let written = match &opts.output.path {
+            None => std::io::stdout().write_all(svg.as_bytes()),
+            Some(p) => std::fs::write(p, svg),
+        };
+        return written.map_err(|e| e.to_string());
+    }
+
+    // the background and the digits for sums are two more symbols
+    colors.push((255, 255, 255));
+    colors.push((0, 0, 0));
+
+    let mut rows = icon.rows(columns);
+    let tty = opts.output.is_stdout() && std::io::stdout().is_terminal();
+
+    // blocks can't tell the symbols apart without color, digits can
+    if opts.output.output == Output::UnicodeAnsi && opts.color.depth(tty) == ColorDepth::NoColor {
+        return icon_text(&rows, opts.radix, &opts.output).map_err(|e| e.to_string());
+    }
+
+    // half blocks pair up rows, an odd one out is paired with background
+    // rather than the writer's symbol 0
+    if opts.output.output == Output::UnicodeAnsi && rows.len() % 2 == 1 {
+        rows.push(vec![opts.radix as Cell; rows[0].len()]);
+    }
+
+    let wopts = WriterOpts {
+        scale: opts.scale,
+        grid: opts.output.output == Output::PNG,
+        palette: PaletteSpec::List(colors),
+        color: opts.color.depth(tty),
+        ..WriterOpts::default()
+    };
+
+    let write = || {
+        let mut w = opts.output.writer(opts.radix + 2, rows[0].len(), rows.len(), &wopts)?;
+        for row in &rows {
+            w.write_line(row)?;
+        }
+        w.finish()
+    };
+    write().map_err(|e| e.to_string())
+}
+
+fn icon_text(rows: &[Lattice], radix: u32, output: &OutputSpec) -> std::io::Result<()> {
+    let mut text = String::new();
+    for row in rows {
+        for c in row {
+            text.push(match *c as u32 {
+                c if c == radix => ' ',
+                c if c == radix + 1 => '#',
+                _ => from_digit(c),
+            });
+        }
+        text.push('\n');
+    }
+
+    match &output.path {
+        None => std::io::stdout().write_all(text.as_bytes()),
+        Some(p) => std::fs::write(p, text),
+    }
+}
+
+/// Run an experiment from a TOML run file, as written by --save
+#[derive(Parser, Debug)]
+#[clap(name = "ca1d run")]
+#[clap(after_help = "run files have radix, neighborhood and rule, and optionally start, config_file, \
+mask, border, width, from, to, seed, density and outputs, named like the ca1d options")]
+struct RunOpts {
+    /// TOML run file
+    file: PathBuf,
+
+    /// more ca1d options, which replace the file's
+    #[clap(allow_hyphen_values = true, trailing_var_arg = true)]
+    rest: Vec<String>,
+}
+
+// what makes a run, the rest is how it looks
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct RunFile {
+    radix: u32,
+    neighborhood: u32,
+    rule: String,
+    start: Option<String>,
+    config_file: Option<PathBuf>,
+    mask: Option<String>,
+    border: Option<String>,
+    width: Option<usize>,
+    from: Option<usize>,
+    to: Option<usize>,
+    seed: Option<u64>,
+    density: Option<Vec<f64>>,
+    outputs: Option<Vec<String>>,
+}
+
+impl RunFile {
+    fn new(opts: &Opts) -> Self {
+        Self {
+            radix: opts.radix,
+            neighborhood: opts.nabor_size,
+            rule: opts.rule_number.to_string(),
+            start: Some(opts.start_config.clone()),
+            config_file: None,
+            mask: Some(opts.nabor_mask.clone()).filter(|m| m != "0"),
+            border: Some(opts.border.to_string()),
+            width: Some(opts.width),
+            from: Some(opts.from),
+            to: Some(opts.to),
+            seed: opts.seed,
+            density: Some(opts.density.clone()).filter(|d| !d.is_empty()),
+            outputs: Some(opts.output.iter().map(|o| o.to_string()).collect()),
+        }
+    }
+
+    // leaving out the options in skip
+    fn args(&self, skip: &[String]) -> Vec<String> {
+        let mut args = vec![
+            "ca1d".to_string(),
+            self.radix.to_string(),
+            self.neighborhood.to_string(),
+            self.rule.clone(),
+        ];
+        args.extend(self.start.clone());
+
+        let mut opt = |name: &str, value: Option<String>| {
+            if let Some(v) = value.filter(|_| !skip.iter().any(|s| s == name)) {
+                args.push(format!("--{}", name));
+                args.push(v);
+            }
+        };
+        opt("config-file", self.config_file.as_ref().map(|p| p.display().to_string()));
+        opt("nabor-mask", self.mask.clone());
+        opt("border", self.border.clone());
+        opt("width", self.width.map(|w| w.to_string()));
+        opt("from", self.from.map(|f| f.to_string()));
+        opt("to", self.to.map(|t| t.to_string()));
+        opt("seed", self.seed.map(|s| s.to_string()));
+        opt(
+            "density",
+            self.density.as_ref().map(|d| d.iter().map(f64::to_string).collect::<Vec<_>>().join(",")),
+        );
+        for o in self.outputs.iter().flatten() {
+            opt("output", Some(o.clone()));
+        }
+
+        args
+    }
+}
+
+// the long names of the options in args
+fn option_names(args: &[String]) -> Vec<String> {
+    let cmd = Opts::command();
+    args.iter()
+        .filter_map(|a| match a.strip_prefix("--") {
+            Some(long) => long.split('=').next().map(String::from),
+            None => {
+                let short = a.strip_prefix('-')?.chars().next()?;
+                let arg = cmd.get_arguments().find(|o| o.get_short() == Some(short))?;
+                arg.get_long().map(String::from)
+            }
+        })
+        .collect()
+}
+
+// ca1d's arguments for a run file, with any options given after it in place
+// of the file's
+pub fn run_args(args: &[String]) -> Result<Vec<String>, String> {
+    let opts = RunOpts::parse_from(args);
+
+    let text = std::fs::read_to_string(&opts.file).map_err(|e| format!("{}: {}", opts.file.display(), e))?;
+    let mut run: RunFile = toml::from_str(&text).map_err(|e| format!("{}: {}", opts.file.display(), e))?;
+
+    // a config file is found next to the run file
+    if let (Some(path), Some(dir)) = (&run.config_file, opts.file.parent()) {
+        if path.is_relative() && path.as_os_str() != "-" {
+            run.config_file = Some(dir.join(path));
+        }
+    }
+    let mut args = run.args(&option_names(&opts.rest));
+    args.extend(opts.rest);
+    Ok(args)
+}
+
+// the run file for these options, headed by the matching command line. The
+// width is written out as it is, so a random start comes back the same
+pub fn save(opts: &Opts, path: &Path) -> Result<(), String> {
+    let opts = Opts {
+        width: opts.width(),
+        ..opts.clone()
+    };
+    let run = toml::to_string(&RunFile::new(&opts)).map_err(|e| e.to_string())?;
+    std::fs::write(path, format!("# {}\n{}", cmd_line(&opts), run)).map_err(|e| e.to_string())
+}
@@ -0,0 +1,63 @@
+// CSV with a row per generation: its cells as a digit string and statistics.
+// The run is described in leading # lines, eg pandas.read_csv(f, comment='#')
+use std::io::{self, Write};
+
+use crate::stats::Stats;
+use crate::{from_digit, CAWriter, Lattice, WriterOpts};
+
+pub struct CsvWriter<W: Write> {
+    w: W,
+    stats: Stats,
+    generation: usize,
+    sbuf: String,
+}
+impl<W: Write> CsvWriter<W> {
+    pub fn new(mut w: W, radix: u32, width: usize, opts: &WriterOpts) -> io::Result<Self> {
+        let run = &opts.run;
+
+        writeln!(w, "# cmd_line: {}", run.cmd_line)?;
+        if let Some(seed) = run.seed {
+            writeln!(w, "# seed: {}", seed)?;
+        }
+        writeln!(w, "# radix: {}", radix)?;
+        writeln!(w, "# nabor_size: {}", run.nabor_size)?;
+        writeln!(w, "# rule: {}", run.rule.canonical(radix, run.nabor_size))?;
+        writeln!(w, "# border: {}", run.border)?;
+        writeln!(w, "# width: {}", width)?;
+
+        write!(w, "generation,cells,runs,hamming")?;
+        for s in 0..radix {
+            write!(w, ",density_{}", s)?;
+        }
+        writeln!(w)?;
+
+        Ok(Self {
+            w,
+            stats: Stats::new(radix),
+            generation: 0,
+            sbuf: String::with_capacity(width),
+        })
+    }
+}
+impl<W: Write> CAWriter for CsvWriter<W> {
+    fn write_line(&mut self, v: &Lattice) -> io::Result<()> {
+        let stats = self.stats.update(v);
+
+        self.sbuf.clear();
+        self.sbuf.extend(v.iter().map(from_digit));
+
+        write!(self.w, "{},{},{},", self.generation, self.sbuf, stats.runs)?;
+        if let Some(h) = stats.hamming {
+            write!(self.w, "{}", h)?;
+        }
+        for d in &stats.density {
+            write!(self.w, ",{}", d)?;
+        }
+        self.generation += 1;
+        writeln!(self.w)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.w.flush()
+    }
+}
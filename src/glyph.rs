@@ -0,0 +1,135 @@
+use std::io;
+use std::io::Write;
+
+use crate::{CAWriter, Cell, Lattice, CELL0};
+
+// 4x4 ordered dither thresholds
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+// bit for each dot of a 2x4 braille cell, indexed [y][x]
+const BRAILLE: [[u8; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+// indexed by mask of top left, top right, bottom left, bottom right
+const QUADRANTS: [char; 16] = [
+    ' ', '▘', '▝', '▀', '▖', '▌', '▞', '▛', '▗', '▚', '▐', '▜', '▄', '▙', '▟', '█',
+];
+
+fn braille(mask: u8) -> char {
+    char::from_u32(0x2800 + mask as u32).unwrap()
+}
+
+fn quadrant(mask: u8) -> char {
+    QUADRANTS[mask as usize]
+}
+
+// Packs a block of cells, several from each of several generations, into
+// one character. Each cell is a dot that is either on or off, for radix > 2
+// the cells are ordered dithered so higher symbols have more dots on
+pub struct GlyphWriter<W: Write> {
+    w: W,
+    radix: u32,
+    width: usize,
+    // cells per glyph across and down
+    cols: usize,
+    rows: usize,
+    // dot mask to character, bits are row major from the top left
+    glyph: fn(u8) -> char,
+    // rows generations waiting to be written
+    cells: Vec<Cell>,
+    row: usize,
+    generation: usize,
+    sbuf: String,
+}
+
+impl<W: Write> GlyphWriter<W> {
+    fn new(w: W, radix: u32, width: usize, (cols, rows): (usize, usize), glyph: fn(u8) -> char) -> Self {
+        Self {
+            w,
+            radix,
+            width,
+            cols,
+            rows,
+            glyph,
+            cells: vec![CELL0; width * rows],
+            row: 0,
+            generation: 0,
+            sbuf: String::with_capacity(width),
+        }
+    }
+
+    // 2x4 cells per character
+    pub fn braille(w: W, radix: u32, width: usize) -> Self {
+        Self::new(w, radix, width, (2, 4), |mask| {
+            let mut dots = 0;
+            for (y, bits) in BRAILLE.iter().enumerate() {
+                for (x, bit) in bits.iter().enumerate() {
+                    if mask & 1 << (y * 2 + x) != 0 {
+                        dots |= bit;
+                    }
+                }
+            }
+            braille(dots)
+        })
+    }
+
+    // 2x2 cells per character
+    pub fn quadrant(w: W, radix: u32, width: usize) -> Self {
+        Self::new(w, radix, width, (2, 2), quadrant)
+    }
+
+    // 1x2 cells per character, UnicodeAnsi without the colors
+    pub fn half(w: W, radix: u32, width: usize) -> Self {
+        Self::new(w, radix, width, (1, 2), |mask| [' ', '▀', '▄', '█'][mask as usize])
+    }
+
+    fn dot(&self, c: Cell, x: usize, y: usize) -> bool {
+        let threshold = BAYER[y % 4][x % 4] as u32 * 2 + 1;
+        // c / (radix - 1) > threshold / 32
+        c as u32 * 32 > threshold * (self.radix - 1)
+    }
+
+    fn write_glyphs(&mut self) -> io::Result<()> {
+        for gx in 0..self.width.div_ceil(self.cols) {
+            let mut mask = 0;
+
+            for dy in 0..self.rows {
+                for dx in 0..self.cols {
+                    let x = gx * self.cols + dx;
+                    if x < self.width && self.dot(self.cells[dy * self.width + x], x, self.generation + dy) {
+                        mask |= 1 << (dy * self.cols + dx);
+                    }
+                }
+            }
+
+            self.sbuf.push((self.glyph)(mask));
+        }
+
+        self.row = 0;
+        self.generation += self.rows;
+        self.cells.fill(CELL0);
+
+        let r = writeln!(self.w, "{}", self.sbuf);
+        self.sbuf.clear();
+        r
+    }
+}
+
+impl<W: Write> CAWriter for GlyphWriter<W> {
+    fn write_line(&mut self, v: &Lattice) -> io::Result<()> {
+        self.cells[self.row * self.width..(self.row + 1) * self.width].copy_from_slice(v);
+        self.row += 1;
+
+        if self.row == self.rows {
+            return self.write_glyphs();
+        }
+        Ok(())
+    }
+
+    // a partly filled line is written out with the rest off
+    fn finish(&mut self) -> io::Result<()> {
+        if self.row > 0 {
+            self.write_glyphs()?;
+        }
+        self.w.flush()
+    }
+}
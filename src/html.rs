@@ -0,0 +1,166 @@
+// A single self contained HTML page for a run: its parameters, the rule icon,
+// summary statistics and the diagram as an embedded PNG
+use std::io::{self, Write};
+
+use crate::stats::Stats;
+use crate::{get_writer, CAWriter, Lattice, Output, Rgb, RuleIcon, Spool, WriterOpts};
+use crate::palette::css;
+
+// the diagram is stretched to about this many pixels across
+const VIEW_WIDTH: usize = 800;
+
+pub struct HtmlWriter<W: Write> {
+    w: W,
+    png: Box<dyn CAWriter>,
+    spool: Spool,
+    png_width: usize,
+    colors: Vec<Rgb>,
+    stats: Stats,
+    generations: usize,
+    // running sums for the averages
+    density: Vec<f64>,
+    last_density: Vec<f64>,
+    runs: usize,
+    hamming: usize,
+}
+
+impl<W: Write> HtmlWriter<W> {
+    // the page is written as the run starts and ends, the PNG is kept in
+    // memory in between
+    pub fn new(mut w: W, radix: u32, width: usize, hite: usize, opts: &WriterOpts) -> io::Result<Self> {
+        let run = &opts.run;
+        let rule = run.rule.canonical(radix, run.nabor_size);
+        let colors = opts.palette.io_colors(radix)?;
+
+        writeln!(w, "<!DOCTYPE html>")?;
+        writeln!(w, r#"<html><head><meta charset="utf-8"><title>ca1d {} {} {}</title>"#, radix, run.nabor_size, rule)?;
+        writeln!(
+            w,
+            "<style>body {{ font-family: sans-serif; margin: 2em }} td, th {{ padding: 0 1em 0 0; text-align: left }} \
+             img {{ image-rendering: pixelated }} .swatch {{ display: inline-block; width: 1em; height: 1em; border: 1px solid #888 }}</style>"
+        )?;
+        writeln!(w, "</head><body>")?;
+        writeln!(w, "<h1>{}, radix {}, {} neighbors</h1>", rule, radix, run.nabor_size)?;
+
+        writeln!(w, "<h2>Parameters</h2>\n<table>")?;
+        if !run.cmd_line.is_empty() {
+            writeln!(w, "<tr><th>command</th><td><code>{}</code></td></tr>", escape(&run.cmd_line))?;
+        }
+        writeln!(w, "<tr><th>radix</th><td>{}</td></tr>", radix)?;
+        writeln!(w, "<tr><th>neighborhood</th><td>{}</td></tr>", run.nabor_size)?;
+        writeln!(w, "<tr><th>rule</th><td>{}</td></tr>", rule)?;
+        writeln!(w, "<tr><th>border</th><td>{}</td></tr>", run.border)?;
+        writeln!(w, "<tr><th>width</th><td>{}</td></tr>", width)?;
+        writeln!(w, "<tr><th>generations</th><td>{}</td></tr>", hite)?;
+        if let Some(seed) = run.seed {
+            writeln!(w, "<tr><th>seed</th><td>{}</td></tr>", seed)?;
+        }
+        writeln!(w, "</table>")?;
+
+        writeln!(w, "<h2>Rule</h2>")?;
+        match RuleIcon::new(&run.to_ca(radix)) {
+            Ok(icon) => write!(w, "{}", icon.svg(&colors, icon.columns(80)))?,
+            Err(e) => writeln!(w, "<p>{}</p>", e)?,
+        }
+
+        let spool = Spool::default();
+        let png = get_writer(Output::PNG, spool.clone(), radix, width, hite, opts)?;
+        let png_width = if opts.transpose { hite } else { width } * opts.scale;
+
+        Ok(Self {
+            w,
+            png,
+            spool,
+            png_width,
+            colors,
+            stats: Stats::new(radix),
+            generations: 0,
+            density: vec![0.0; radix as usize],
+            last_density: vec![0.0; radix as usize],
+            runs: 0,
+            hamming: 0,
+        })
+    }
+
+    fn write_stats(&mut self) -> io::Result<()> {
+        let n = self.generations.max(1) as f64;
+
+        writeln!(self.w, "<h2>Statistics</h2>\n<table>")?;
+        writeln!(self.w, "<tr><th>symbol</th><th></th><th>mean density</th><th>final density</th></tr>")?;
+        for (s, c) in self.colors.iter().enumerate() {
+            writeln!(
+                self.w,
+                r#"<tr><td>{}</td><td><span class="swatch" style="background: {}"></span></td><td>{:.4}</td><td>{:.4}</td></tr>"#,
+                s,
+                css(*c),
+                self.density[s] / n,
+                self.last_density[s],
+            )?;
+        }
+        writeln!(self.w, "</table>\n<table>")?;
+        writeln!(self.w, "<tr><th>generations</th><td>{}</td></tr>", self.generations)?;
+        writeln!(self.w, "<tr><th>mean runs</th><td>{:.2}</td></tr>", self.runs as f64 / n)?;
+        if self.generations > 1 {
+            let changes = self.hamming as f64 / (self.generations - 1) as f64;
+            writeln!(self.w, "<tr><th>mean Hamming distance</th><td>{:.2}</td></tr>", changes)?;
+        }
+        writeln!(self.w, "</table>")
+    }
+}
+
+impl<W: Write> CAWriter for HtmlWriter<W> {
+    fn write_line(&mut self, v: &Lattice) -> io::Result<()> {
+        let stats = self.stats.update(v);
+        for (sum, d) in self.density.iter_mut().zip(&stats.density) {
+            *sum += d;
+        }
+        self.last_density = stats.density;
+        self.runs += stats.runs;
+        self.hamming += stats.hamming.unwrap_or(0);
+        self.generations += 1;
+
+        self.png.write_line(v)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.png.finish()?;
+        self.write_stats()?;
+
+        let shown = self.png_width * (VIEW_WIDTH / self.png_width.max(1)).max(1);
+        writeln!(self.w, "<h2>Diagram</h2>")?;
+        writeln!(
+            self.w,
+            r#"<img width="{}" alt="space-time diagram" src="data:image/png;base64,{}">"#,
+            shown,
+            base64(&self.spool.take())
+        )?;
+        writeln!(self.w, "</body></html>")?;
+        self.w.flush()
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn base64(data: &[u8]) -> String {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+
+    for chunk in data.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(TABLE[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,194 @@
+// Wolfram style rule icon: each neighborhood in the rule table drawn above the
+// cell it turns into, highest neighborhood first. For codes it is each sum
+use std::fmt::Write;
+
+use crate::palette::css;
+use crate::{CAEvalType, Cell, Lattice, Rgb, CA};
+
+// more than this and the icon stops being something you can look at
+const MAX_ENTRIES: usize = 4096;
+
+// 3x5 digits for sums in the raster icons, rows top to bottom, high bit left
+const DIGIT_HITE: usize = 5;
+const DIGITS: [u16; 10] = [
+    0b111_101_101_101_111,
+    0b010_110_010_010_111,
+    0b111_001_111_100_111,
+    0b111_001_111_001_111,
+    0b101_101_111_001_001,
+    0b111_100_111_001_111,
+    0b111_100_111_101_111,
+    0b111_001_001_001_001,
+    0b111_101_111_101_111,
+    0b111_101_111_001_111,
+];
+
+// cell size and spacing in SVG units
+const CELL: usize = 10;
+const GAP: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pattern {
+    // leftmost neighbor first
+    Cells(Lattice),
+    Sum(usize),
+}
+
+pub struct RuleIcon {
+    pub radix: u32,
+    pub nabor_size: u32,
+    pub entries: Vec<(Pattern, Cell)>,
+}
+
+impl RuleIcon {
+    pub fn new(ca: &CA) -> Result<Self, &'static str> {
+        let radix = ca.rule_order as usize;
+        let nabor_size = ca.nabor_size;
+        let code = matches!(ca.rule.eval_type, CAEvalType::Code(_));
+
+        let count = if code {
+            (radix - 1) * nabor_size as usize + 1
+        } else {
+            radix.checked_pow(nabor_size).unwrap_or(usize::MAX)
+        };
+        if count > MAX_ENTRIES {
+            return Err("rule table too large to draw");
+        }
+
+        let entries = (0..count)
+            .rev()
+            .map(|idx| {
+                let pattern = if code {
+                    Pattern::Sum(idx)
+                } else {
+                    let mut cells = vec![0; nabor_size as usize];
+                    let mut rest = idx;
+                    for c in cells.iter_mut().rev() {
+                        *c = (rest % radix) as Cell;
+                        rest /= radix;
+                    }
+                    Pattern::Cells(cells)
+                };
+                (pattern, ca.rule.eval_idx(idx))
+            })
+            .collect();
+
+        Ok(Self {
+            radix: ca.rule_order,
+            nabor_size,
+            entries,
+        })
+    }
+
+    // how many entries fit across in about `across` cells, at least one
+    pub fn columns(&self, across: usize) -> usize {
+        let per = self.entry_size().0 + 1;
+        (across / per).clamp(1, self.entries.len().max(1))
+    }
+
+    // width and height of an entry in cells: a neighborhood over its output,
+    // or a sum written out in DIGITS over the output
+    fn entry_size(&self) -> (usize, usize) {
+        let sums = self.entries.iter().filter_map(|(p, _)| match p {
+            Pattern::Sum(s) => Some(s.to_string().len() * 4 - 1),
+            Pattern::Cells(_) => None,
+        });
+        match sums.max() {
+            Some(w) => (w, DIGIT_HITE + 2),
+            None => (self.nabor_size as usize, 2),
+        }
+    }
+
+    // the icon as rows of cells for the raster writers, with `radix` as the
+    // background and `radix + 1` the color sums are written in
+    pub fn rows(&self, columns: usize) -> Vec<Lattice> {
+        let bg = self.radix as Cell;
+        let ink = bg + 1;
+        let columns = columns.max(1);
+        let (ew, eh) = self.entry_size();
+        let rows = self.entries.len().div_ceil(columns);
+
+        let mut grid = vec![vec![bg; columns * (ew + 1) - 1]; rows * (eh + 1) - 1];
+
+        for (i, (pattern, out)) in self.entries.iter().enumerate() {
+            let x = (i % columns) * (ew + 1);
+            let y = (i / columns) * (eh + 1);
+
+            match pattern {
+                Pattern::Cells(cells) => grid[y][x..x + cells.len()].copy_from_slice(cells),
+                Pattern::Sum(sum) => {
+                    let digits = sum.to_string();
+                    let left = x + (ew - (digits.len() * 4 - 1)) / 2;
+                    for (d, c) in digits.bytes().enumerate() {
+                        let glyph = DIGITS[(c - b'0') as usize];
+                        for bit in 0..DIGIT_HITE * 3 {
+                            if glyph & (1 << (DIGIT_HITE * 3 - 1 - bit)) != 0 {
+                                grid[y + bit / 3][left + d * 4 + bit % 3] = ink;
+                            }
+                        }
+                    }
+                }
+            }
+            grid[y + eh - 1][x + (ew - 1) / 2] = *out;
+        }
+
+        grid
+    }
+
+    // entries wrap after `columns` a row, palette has a color per symbol
+    pub fn svg(&self, palette: &[Rgb], columns: usize) -> String {
+        let colors: Vec<String> = palette.iter().map(|c| css(*c)).collect();
+        let columns = columns.max(1);
+        let rows = self.entries.len().div_ceil(columns);
+
+        let entry_w = CELL * self.nabor_size as usize;
+        let entry_h = 2 * CELL + CELL / 5;
+        let width = columns * (entry_w + GAP) - GAP;
+        let hite = rows * (entry_h + GAP) - GAP;
+
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">"#,
+            width * 2,
+            hite * 2,
+            width,
+            hite
+        );
+
+        let cell = |svg: &mut String, x: usize, y: usize, c: Cell| {
+            let _ = writeln!(
+                svg,
+                r##"<rect x="{}" y="{}" width="{}" height="{}" fill="{}" stroke="#888" stroke-width="0.5"/>"##,
+                x, y, CELL, CELL, colors[c as usize]
+            );
+        };
+
+        for (i, (pattern, out)) in self.entries.iter().enumerate() {
+            let x = (i % columns) * (entry_w + GAP);
+            let y = (i / columns) * (entry_h + GAP);
+
+            match pattern {
+                Pattern::Cells(cells) => {
+                    for (j, c) in cells.iter().enumerate() {
+                        cell(&mut svg, x + j * CELL, y, *c);
+                    }
+                }
+                Pattern::Sum(sum) => {
+                    let _ = writeln!(
+                        svg,
+                        r#"<text x="{}" y="{}" font-size="{}" font-family="sans-serif" text-anchor="middle">{}</text>"#,
+                        x + entry_w / 2,
+                        y + CELL - 1,
+                        CELL,
+                        sum
+                    );
+                }
+            }
+            cell(&mut svg, x + (entry_w - CELL) / 2, y + CELL + CELL / 5, *out);
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
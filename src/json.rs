@@ -0,0 +1,81 @@
+// JSON lines: an object describing the run, then one per generation with its
+// cells and statistics
+use std::fmt::Display;
+use std::io::{self, Write};
+
+use crate::stats::Stats;
+use crate::{CAWriter, Lattice, WriterOpts};
+
+pub struct JsonWriter<W: Write> {
+    w: W,
+    stats: Stats,
+    generation: usize,
+}
+impl<W: Write> JsonWriter<W> {
+    // hite is the expected generation count, 0 if not known
+    pub fn new(mut w: W, radix: u32, width: usize, hite: usize, opts: &WriterOpts) -> io::Result<Self> {
+        let run = &opts.run;
+        let seed = run.seed.map_or("null".to_string(), |s| s.to_string());
+        let generations = if hite == 0 { "null".to_string() } else { hite.to_string() };
+
+        writeln!(
+            w,
+            r#"{{"cmd_line": {}, "seed": {}, "radix": {}, "nabor_size": {}, "rule": {}, "border": {}, "width": {}, "generations": {}}}"#,
+            json_str(&run.cmd_line),
+            seed,
+            radix,
+            run.nabor_size,
+            json_str(&run.rule.canonical(radix, run.nabor_size).to_string()),
+            json_str(&run.border.to_string()),
+            width,
+            generations,
+        )?;
+
+        Ok(Self {
+            w,
+            stats: Stats::new(radix),
+            generation: 0,
+        })
+    }
+}
+impl<W: Write> CAWriter for JsonWriter<W> {
+    fn write_line(&mut self, v: &Lattice) -> io::Result<()> {
+        let stats = self.stats.update(v);
+        let hamming = stats.hamming.map_or("null".to_string(), |h| h.to_string());
+
+        writeln!(
+            self.w,
+            r#"{{"generation": {}, "cells": {}, "density": {}, "runs": {}, "hamming": {}}}"#,
+            self.generation,
+            json_list(v),
+            json_list(&stats.density),
+            stats.runs,
+            hamming,
+        )?;
+        self.generation += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.w.flush()
+    }
+}
+
+fn json_list<T: Display>(items: &[T]) -> String {
+    let items: Vec<String> = items.iter().map(|i| i.to_string()).collect();
+    format!("[{}]", items.join(", "))
+}
+
+pub(crate) fn json_str(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
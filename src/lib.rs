@@ -1,15 +1,55 @@
+use std::cell::RefCell;
 use std::collections::BTreeMap;
+use std::fs::File;
 use std::io;
 use std::fmt;
 use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::rc::Rc;
 use std::str::FromStr;
-use std::time::Instant;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 
-use termcolor::{BufferWriter, Color, ColorChoice, ColorSpec, WriteColor};
+use termcolor::{Ansi, Color, WriteColor};
 
 use num_bigint::BigUint;
 use num_traits::cast::ToPrimitive;
 
+mod animation;
+mod color;
+mod csv;
+mod glyph;
+mod html;
+mod icon;
+mod json;
+mod load;
+mod npy;
+mod palette;
+mod pattern;
+mod raster;
+mod raw;
+mod sixel;
+mod stats;
+mod svg;
+pub use animation::GIFWriter;
+pub use color::{ColorDepth, ColorWhen};
+use color::set_colors;
+pub use csv::CsvWriter;
+pub use glyph::GlyphWriter;
+pub use html::HtmlWriter;
+pub use icon::{Pattern, RuleIcon};
+pub use json::JsonWriter;
+pub use load::read_config;
+pub use npy::NpyWriter;
+pub use palette::{PaletteSpec, Rgb};
+pub use pattern::{Align, ConfigPattern, Overflow};
+pub use raw::{RawHeader, RawReader, RawWriter};
+pub use sixel::SixelWriter;
+pub use stats::{GenStats, Stats};
+pub use svg::SVGWriter;
+
+use raster::Raster;
+
 // A cell can be in [0,36], limited by from_digit and string input
 // A lattice is a 1d array of cells
 pub type Cell = u8;
@@ -66,9 +106,6 @@ impl CAEvalType {
 
         let (radix, numportion) = CAEvalType::get_radix(s);
 
-        if numportion == "@" {
-        }
-
         let bn = BigUint::parse_bytes(numportion.as_bytes(), radix);
         match bn {
             None => Err("Failed to parse given rule"),
@@ -82,6 +119,29 @@ impl CAEvalType {
         }
     }
 
+    // rule numbers are taken modulo this: radix^(table entries), where a
+    // Rule has an entry per neighborhood and a Code one per neighborhood sum.
+    // None when there are too many entries to be worth counting
+    pub fn rule_space(&self, radix: u32, nabor_size: u32) -> Option<BigUint> {
+        let entries = match self {
+            CAEvalType::Rule(_) => (radix as usize).checked_pow(nabor_size)?,
+            CAEvalType::Code(_) => (radix as usize - 1) * nabor_size as usize + 1,
+        };
+        if entries > 1 << 22 {
+            return None;
+        }
+        Some(BigUint::from(radix).pow(entries as u32))
+    }
+
+    // the smallest number for the same rule
+    pub fn canonical(&self, radix: u32, nabor_size: u32) -> CAEvalType {
+        match (self, self.rule_space(radix, nabor_size)) {
+            (CAEvalType::Rule(n), Some(space)) => CAEvalType::Rule(n % space),
+            (CAEvalType::Code(n), Some(space)) => CAEvalType::Code(n % space),
+            _ => self.clone(),
+        }
+    }
+
     fn to_bignum(&self) -> &BigUint {
         match self {
             CAEvalType::Code(n) => n,
@@ -120,7 +180,7 @@ impl CAEval {
         }
     }
 
-    fn eval(&self, naborhood: &Lattice) -> Cell {
+    fn eval<I: Iterator<Item = Cell>>(&self, naborhood: I) -> Cell {
         let idx = match self.eval_type {
             CAEvalType::Rule(_) => self.idx_rule(naborhood),
             CAEvalType::Code(_) => self.idx_code(naborhood),
@@ -130,23 +190,20 @@ impl CAEval {
     }
 
     // lowest index == lowest bit
-    // for rule evaluation
-    fn idx_rule(&self, naborhood: &Lattice) -> usize {
-        let mut s = String::new();
-        for i in naborhood {
-            let d = from_digit(i as &Cell);
-            s.push(d);
-        }
+    // for rule evaluation, the neighborhood read as a number in radix,
+    // leftmost neighbor being the most significant digit
+    fn idx_rule<I: Iterator<Item = Cell>>(&self, naborhood: I) -> usize {
+        let radix = self.radix as usize;
 
-        usize::from_str_radix(&s, self.radix).unwrap()
+        naborhood.fold(0, |idx, c| idx * radix + c as usize)
     }
 
     // sum-avg / sum mod code function
-    fn idx_code(&self, naborhood: &Lattice) -> usize {
+    fn idx_code<I: Iterator<Item = Cell>>(&self, naborhood: I) -> usize {
         let mut sum: usize = CELL0 as usize;
 
         for i in naborhood {
-            sum += *i as usize;
+            sum += i as usize;
         }
 
         sum
@@ -192,302 +249,364 @@ pub fn from_char(c: char) -> Cell {
     u8::from_str_radix(&String::from(c), 36).unwrap()
 }
 
-trait CAWriter {
-    fn new(radix: u32, width: usize, hite: usize) -> Self
-    where
-        Self: Sized;
+// Something that consumes generations, one line at a time.
+// Implement this to send automata somewhere other than the built in outputs
+// and drive it with automate_with()
+pub trait CAWriter {
+    fn write_line(&mut self, v: &Lattice) -> io::Result<()>;
 
-    fn write_line(&mut self, v: &Lattice);
+    // called once after the last line, flushes anything still buffered.
+    // done explicitly rather than in Drop so errors can be reported
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
 
-    // given a symbol number in [0,sym_count) , sym_count, and array_len >= sym_count
-    // return and index in [0,array_len) that is linearly "spaced" equally over the array
-    fn idx_scale(sym_num: u8, sym_count: u32, array_len: usize) -> usize where Self: Sized {
-        let sym_num: usize = sym_num as usize;
-        let sym_count: usize = sym_count as usize;
+// given a symbol number in [0,sym_count) , sym_count, and array_len >= sym_count
+// return and index in [0,array_len) that is linearly "spaced" equally over the array
+fn idx_scale(sym_num: u8, sym_count: u32, array_len: usize) -> usize {
+    let sym_num: usize = sym_num as usize;
+    let sym_count: usize = sym_count as usize;
 
-        let space = array_len / (sym_count - 1);
-        let idx = usize::min(sym_num * space, array_len - 1);
+    let space = array_len / (sym_count - 1);
+    let idx = usize::min(sym_num * space, array_len - 1);
 
-        assert!(sym_count > 1);
-        assert!(array_len > 1);
-        assert!(sym_num < sym_count);
-        assert!(sym_count <= array_len);
-
-        idx
-    }
+    assert!(sym_count > 1);
+    assert!(array_len > 1);
+    assert!(sym_num < sym_count);
+    assert!(sym_count <= array_len);
 
+    idx
 }
 
-struct NullWriter {}
-impl CAWriter for NullWriter {
-    fn new(_radix: u32, _width: usize, _hite: usize) -> Self {
+pub struct NullWriter {}
+impl NullWriter {
+    pub fn new() -> Self {
         Self {}
     }
-    fn write_line(&mut self, _v: &Lattice) {}
 }
-
-struct RawWriter {}
-impl CAWriter for RawWriter {
-    fn new(_radix: u32, _width: usize, _hite: usize) -> Self {
-        Self {}
+impl Default for NullWriter {
+    fn default() -> Self {
+        Self::new()
     }
-
-    #[allow(unused_must_use)]
-    fn write_line(&mut self, v: &Lattice) {
-        io::stdout().write_all(v);
+}
+impl CAWriter for NullWriter {
+    fn write_line(&mut self, _v: &Lattice) -> io::Result<()> {
+        Ok(())
     }
 }
 
-struct CellWriter {
+pub struct CellWriter<W: Write> {
+    w: W,
     sbuf: String,
 }
-impl CAWriter for CellWriter {
-    fn new(_radix: u32, width: usize, _hite: usize) -> Self {
+impl<W: Write> CellWriter<W> {
+    pub fn new(w: W, width: usize) -> Self {
         Self {
+            w,
             sbuf: String::with_capacity(width),
         }
     }
-
-    fn write_line(&mut self, v: &Lattice) {
+}
+impl<W: Write> CAWriter for CellWriter<W> {
+    fn write_line(&mut self, v: &Lattice) -> io::Result<()> {
         for i in v {
             let c = from_digit(i);
             self.sbuf.push(c);
         }
-        println!("{}", self.sbuf);
+        let r = writeln!(self.w, "{}", self.sbuf);
         self.sbuf.clear();
+        r
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.w.flush()
     }
 }
 
-struct AsciiWriter {
+pub struct AsciiWriter<W: Write> {
+    w: W,
     symbols: [char; 5],
     radix: u32,
     sbuf: String,
 }
-impl CAWriter for AsciiWriter {
-    fn new(radix: u32, width: usize, _hite: usize) -> Self {
+impl<W: Write> AsciiWriter<W> {
+    pub fn new(w: W, radix: u32, width: usize) -> Self {
         assert!(radix <= 4);
 
         Self {
+            w,
             symbols: [' ', '-', '=', '#', '@'],
             sbuf: String::with_capacity(width),
             radix,
         }
     }
-
-    fn write_line(&mut self, v: &Lattice) {
+}
+impl<W: Write> CAWriter for AsciiWriter<W> {
+    fn write_line(&mut self, v: &Lattice) -> io::Result<()> {
         for i in v {
-            let idx: usize = Self::idx_scale(*i, self.radix, self.symbols.len());
+            let idx: usize = idx_scale(*i, self.radix, self.symbols.len());
             self.sbuf.push(self.symbols[idx]);
         }
-        println!("{}", self.sbuf);
+        let r = writeln!(self.w, "{}", self.sbuf);
         self.sbuf.clear();
+        r
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.w.flush()
     }
 }
 
-struct UnicodeWriter {
-    ascii_writer: AsciiWriter,
+pub struct UnicodeWriter<W: Write> {
+    ascii_writer: AsciiWriter<W>,
 }
-impl CAWriter for UnicodeWriter {
-    fn new(radix: u32, width: usize, _hite: usize) -> Self {
+impl<W: Write> UnicodeWriter<W> {
+    pub fn new(w: W, radix: u32, width: usize) -> Self {
         Self {
             ascii_writer: AsciiWriter {
+                w,
                 symbols: [' ', '░', '▒', '▓', '█'],
                 sbuf: String::with_capacity(width),
                 radix,
             },
         }
     }
-
-    fn write_line(&mut self, v: &Lattice) {
+}
+impl<W: Write> CAWriter for UnicodeWriter<W> {
+    fn write_line(&mut self, v: &Lattice) -> io::Result<()> {
         self.ascii_writer.write_line(v)
     }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.ascii_writer.finish()
+    }
 }
 
-struct AnsiGreyWriter {
-    bufwtr: termcolor::BufferWriter,
+pub struct AnsiGreyWriter<W: Write> {
+    w: Ansi<W>,
     greys: Vec<Color>,
     radix: u32,
 }
-impl CAWriter for AnsiGreyWriter {
-    fn new(radix: u32, _width: usize, _hite: usize) -> Self {
+impl<W: Write> AnsiGreyWriter<W> {
+    // depth is what the terminal can do, it must have some color
+    pub fn new(w: W, radix: u32, depth: ColorDepth) -> Self {
         let mut greyscale = Vec::new();
 
         for c in 232..=255 {
-            greyscale.push(Color::Ansi256(c));
+            if depth >= ColorDepth::Ansi256 {
+                greyscale.push(Color::Ansi256(c));
+            } else {
+                let level = 8 + 10 * (c - 232);
+                greyscale.push(depth.quantize((level, level, level)).unwrap());
+            }
         }
 
         Self {
-            bufwtr: BufferWriter::stdout(ColorChoice::Always),
+            w: Ansi::new(w),
             greys: greyscale,
             radix,
         }
     }
-
-    #[allow(unused_must_use)]
-    fn write_line(&mut self, v: &Lattice) {
-        let mut buffer = self.bufwtr.buffer();
-
+}
+impl<W: Write> CAWriter for AnsiGreyWriter<W> {
+    fn write_line(&mut self, v: &Lattice) -> io::Result<()> {
         for i in v {
-            let idx: usize = Self::idx_scale(*i, self.radix, self.greys.len());
-            buffer.set_color(ColorSpec::new().set_bg(Some(self.greys[idx])));
-            write!(&mut buffer, " ");
+            let idx: usize = idx_scale(*i, self.radix, self.greys.len());
+            set_colors(&mut self.w, None, Some(self.greys[idx]))?;
+            write!(self.w, " ")?;
         }
 
-        buffer.reset();
-        writeln!(&mut buffer);
+        self.w.reset()?;
+        writeln!(self.w)
+    }
 
-        self.bufwtr.print(&buffer);
+    // lines end reset already, this is for when one was cut short
+    fn finish(&mut self) -> io::Result<()> {
+        self.w.reset()?;
+        self.w.flush()
     }
 }
 
-struct UnicodeAnsiWriter {
-    bufwtr: termcolor::BufferWriter,
+pub struct UnicodeAnsiWriter<W: Write> {
+    w: Ansi<W>,
     colors: Vec<Color>,
-    radix: u32,
-    config: Option<Lattice>,
+    // every other line is held here until its partner shows up
+    top: Lattice,
+    pending: bool,
 }
-impl CAWriter for UnicodeAnsiWriter {
-    fn new(radix: u32, _width: usize, _hite: usize) -> Self {
-        let mut colors = Vec::new();
-
-        for c in 0..radix as u8 {
-            let (r, g, b) = cell_to_rgb(c, radix);
-            colors.push(Color::Rgb(r, g, b));
-        }
+impl<W: Write> UnicodeAnsiWriter<W> {
+    // palette has a color for each symbol, quantized to what the terminal
+    // can show. depth must have some color
+    pub fn new(w: W, palette: &[Rgb], depth: ColorDepth) -> Self {
+        let colors = palette.iter().map(|c| depth.quantize(*c).unwrap()).collect();
 
         Self {
-            bufwtr: BufferWriter::stdout(ColorChoice::Always),
+            w: Ansi::new(w),
             colors,
-            radix,
-            config: None,
+            top: Lattice::new(),
+            pending: false,
         }
     }
+}
+impl<W: Write> CAWriter for UnicodeAnsiWriter<W> {
+    fn write_line(&mut self, v: &Lattice) -> io::Result<()> {
+        if !self.pending {
+            self.top.clear();
+            self.top.extend_from_slice(v);
+            self.pending = true;
+            return Ok(());
+        }
+        self.pending = false;
 
-    #[allow(unused_must_use)]
-    fn write_line(&mut self, v: &Lattice) {
-        let mut buffer = self.bufwtr.buffer();
-
-        let top = match &self.config {
-            Some(t) => t.to_vec(),
-            None => {
-                self.config = Some(v.to_vec());
-                return;
-            }
-        };
-
-        for (i, _) in top.iter().enumerate() {
-            let idx_top: usize = Self::idx_scale(top[i], self.radix, self.colors.len());
-            let idx_bot: usize = Self::idx_scale(v[i], self.radix, self.colors.len());
-            buffer.set_color(
-                ColorSpec::new()
-                    .set_fg(Some(self.colors[idx_top]))
-                    .set_bg(Some(self.colors[idx_bot])),
-            );
+        for (i, _) in self.top.iter().enumerate() {
+            set_colors(
+                &mut self.w,
+                Some(self.colors[self.top[i] as usize]),
+                Some(self.colors[v[i] as usize]),
+            )?;
 
-            write!(&mut buffer, "▀");
+            write!(self.w, "▀")?;
         }
 
-        buffer.reset();
-        writeln!(&mut buffer);
-
-        self.bufwtr.print(&buffer);
-        self.config = None;
+        self.w.reset()?;
+        writeln!(self.w)
     }
-}
 
-impl Drop for UnicodeAnsiWriter {
     // we could have a previously written line cached, flush it here
-    fn drop(&mut self) {
-        if let Some(v) = &self.config {
-            let pad: Lattice = [CELL0].repeat(v.len());
-            self.write_line(&pad);
+    fn finish(&mut self) -> io::Result<()> {
+        if self.pending {
+            let pad: Lattice = [CELL0].repeat(self.top.len());
+            self.write_line(&pad)?;
         }
+        self.w.reset()?;
+        self.w.flush()
     }
 }
 
-struct PNGWriter {
-    fd: png::Writer<BufWriter<std::io::Stdout>>,
-    radix: u32,
-    lines: Vec<u8>
+// png's StreamWriter only writes the IEND trailer when it is dropped, which
+// would swallow any error. So png writes into this spool and PNGWriter copies
+// it out to the real destination, where errors get reported
+#[derive(Clone, Default)]
+pub(crate) struct Spool(Rc<RefCell<Vec<u8>>>);
+
+impl Spool {
+    pub(crate) fn take(&self) -> Vec<u8> {
+        self.0.take()
+    }
 }
-impl CAWriter for PNGWriter {
-    fn new(radix: u32, width: usize, hite: usize) -> Self {
-        let w: std::io::BufWriter<std::io::Stdout> = BufWriter::new(std::io::stdout());
-        let mut encoder = png::Encoder::new(w, width as u32, hite as u32);
-        encoder.set_color(png::ColorType::Rgb);
-        encoder.set_depth(png::BitDepth::Eight);
-        let writer = encoder.write_header().unwrap();
 
-        Self {
-            fd: writer,
-            radix,
-            lines: Vec::new()
-        }
+impl Write for Spool {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
     }
 
-    // TODO: optimize
-    #[allow(unused_must_use)]
-    fn write_line(&mut self, v: &Lattice) {
-        for i in v {
-            let (r, g, b) = cell_to_rgb(*i, self.radix);
-            self.lines.push(r);
-            self.lines.push(g);
-            self.lines.push(b);
-        }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
     }
 }
 
-// Or can we ise a StreamWriter or something?
-impl Drop for PNGWriter {
-    fn drop(&mut self) {
-        self.fd.write_image_data(&self.lines).unwrap()
+// bits per pixel for an indexed image with this many colors
+fn png_depth(colors: usize) -> (png::BitDepth, usize) {
+    match colors {
+        0..=2 => (png::BitDepth::One, 1),
+        3..=4 => (png::BitDepth::Two, 2),
+        5..=16 => (png::BitDepth::Four, 4),
+        _ => (png::BitDepth::Eight, 8),
     }
 }
 
-fn to_base_triple(c: Cell, radix: u32) -> Vec<f32> {
-    let mut x: u32 = c.into();
-    let mut result = Vec::new();
+// bits per cell when packing, as for png_depth
+fn cell_bits(radix: u32) -> usize {
+    png_depth(radix as usize).1
+}
 
-    loop {
-        let m = x % radix;
-        x /= radix;
+// cells into bits per cell, first cell in the high bits
+fn pack_cells(cells: &[Cell], bits: usize, out: &mut [u8]) {
+    let per_byte = 8 / bits;
 
-        result.push(m as f32 / (radix - 1) as f32);
+    out.fill(0);
+    for (i, c) in cells.iter().enumerate() {
+        let shift = 8 - bits * (i % per_byte + 1);
+        out[i / per_byte] |= c << shift;
+    }
+}
 
-        if x == 0 {
-            break;
+// Indexed color PNG streamed out a row at a time, the bit depth is the
+// smallest that fits the palette
+pub struct PNGWriter<W: Write> {
+    w: W,
+    spool: Spool,
+    // taken by finish(), StreamWriter::finish() consumes itself
+    stream: Option<png::StreamWriter<'static, Spool>>,
+    raster: Raster,
+    bits: usize,
+    pixels: Vec<Cell>,
+    row: Vec<u8>,
+}
+impl<W: Write> PNGWriter<W> {
+    pub fn new(w: W, radix: u32, width: usize, hite: usize, opts: &WriterOpts) -> io::Result<Self> {
+        let raster = Raster::new(radix, opts)?;
+        let colors = raster.palette();
+        let (depth, bits) = png_depth(colors.len());
+        let (width, hite) = (width * raster.scale, hite * raster.scale);
+
+        let mut palette = Vec::new();
+        for (r, g, b) in colors {
+            palette.extend_from_slice(&[r, g, b]);
         }
-    }
 
-    while result.len() <= 3 {
-        result.push(0.0);
+        let spool = Spool::default();
+        let mut encoder = png::Encoder::new(spool.clone(), width as u32, hite as u32);
+        encoder.set_color(png::ColorType::Indexed);
+        encoder.set_depth(depth);
+        encoder.set_palette(palette);
+        let stream = encoder.write_header()?.into_stream_writer()?;
+
+        let mut writer = Self {
+            w,
+            spool,
+            stream: Some(stream),
+            raster,
+            bits,
+            pixels: vec![0; width],
+            row: vec![0; (width * bits).div_ceil(8)],
+        };
+        writer.drain()?;
+
+        Ok(writer)
     }
 
-    result
+    fn drain(&mut self) -> io::Result<()> {
+        let mut buf = self.spool.0.borrow_mut();
+        self.w.write_all(&buf)?;
+        buf.clear();
+        Ok(())
+    }
 }
+impl<W: Write> CAWriter for PNGWriter<W> {
+    fn write_line(&mut self, v: &Lattice) -> io::Result<()> {
+        for i in 0..self.raster.scale {
+            self.raster.row(v, i, &mut self.pixels);
+            pack_cells(&self.pixels, self.bits, &mut self.row);
+
+            if let Some(stream) = &mut self.stream {
+                stream.write_all(&self.row)?;
+            }
+        }
+        self.drain()
+    }
 
-// covers up to 64 symbols..base 7 will bring to 343
-// convert the given cell [0,255] into a differently based digit that fits into three
-// symbols, ie 10 -> 130 (10 in base 3)
-// then scale the R,G,B values by this triple.
-// makes for maximally distant, but somewhat ugly colors
-fn cell_to_rgb(c: Cell, radix: u32) -> (u8, u8, u8) {
-    assert!(radix <= 64);
-    let base = if radix < 8 {
-        2
-    } else if radix < 27 {
-        3
-    } else {
-        4
-    };
-
-    let t = to_base_triple(c, base);
-
-    (
-        (t[0] * 255.0) as u8,
-        (t[1] * 255.0) as u8,
-        (t[2] * 255.0) as u8,
-    )
+    fn finish(&mut self) -> io::Result<()> {
+        if let Some(stream) = self.stream.take() {
+            // errors if fewer rows than promised were written,
+            // the drop afterwards writes IEND into the spool
+            stream.finish()?;
+        }
+        self.drain()?;
+        self.w.flush()
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
@@ -500,18 +619,161 @@ pub enum Output {
     Cell,
     PNG,
     Raw,
+    SVG,
+    GIF,
+    Sixel,
+    Braille,
+    Quadrant,
+    Npy,
+    Json,
+    Csv,
+    Html,
 }
 
-fn get_printer(o: Output, radix: u32, width: usize, hite: usize) -> Box<dyn CAWriter> {
-    match o {
-        Output::Null => Box::new(NullWriter::new(radix, width, hite)),
-        Output::Cell => Box::new(CellWriter::new(radix, width, hite)),
-        Output::Ascii => Box::new(AsciiWriter::new(radix, width, hite)),
-        Output::Unicode => Box::new(UnicodeWriter::new(radix, width, hite)),
-        Output::AnsiGrey => Box::new(AnsiGreyWriter::new(radix, width, hite)),
-        Output::UnicodeAnsi => Box::new(UnicodeAnsiWriter::new(radix, width, hite)),
-        Output::PNG => Box::new(PNGWriter::new(radix, width, hite)),
-        Output::Raw => Box::new(RawWriter::new(radix, width, hite)),
+impl Output {
+    // terminal outputs can pack several cells into a character,
+    // returns (cells across, generations down) per character
+    pub fn packing(&self) -> (usize, usize) {
+        match self {
+            Output::UnicodeAnsi => (1, 2),
+            Output::Braille => (2, 4),
+            Output::Quadrant => (2, 2),
+            _ => (1, 1),
+        }
+    }
+
+    // the character outputs have only so many symbols
+    pub fn supports(&self, radix: u32) -> bool {
+        match self {
+            Output::Ascii => radix <= 4,
+            Output::Unicode => radix <= 5,
+            _ => true,
+        }
+    }
+}
+
+// knobs for writers beyond radix and size, mostly for the image outputs
+#[derive(Debug, Clone)]
+pub struct WriterOpts {
+    // pixels (or svg units) per cell
+    pub scale: usize,
+    // draw lines between cells
+    pub grid: bool,
+    // animation frame delay in 1/100ths of a second
+    pub delay: u16,
+    // times to play an animation, 0 is forever
+    pub loops: u16,
+    // generations shown per animation frame
+    pub window: usize,
+    // time runs left to right instead of top to bottom
+    pub transpose: bool,
+    pub palette: PaletteSpec,
+    // what the destination can show, the color terminal outputs fall back
+    // to plain characters with NoColor
+    pub color: ColorDepth,
+    // Raw: bit pack cells when the radix is small enough
+    pub pack: bool,
+    // what is being run, for outputs that record it
+    pub run: RunInfo,
+}
+
+impl Default for WriterOpts {
+    fn default() -> Self {
+        Self {
+            scale: 1,
+            grid: false,
+            delay: 10,
+            loops: 0,
+            window: 1,
+            transpose: false,
+            palette: PaletteSpec::default(),
+            color: ColorDepth::TrueColor,
+            pack: false,
+            run: RunInfo::default(),
+        }
+    }
+}
+
+// the parts of a CA worth writing down, radix aside, plus how the run was
+// started for outputs that describe it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunInfo {
+    pub nabor_size: u32,
+    pub rule: CAEvalType,
+    pub border: Border,
+    pub cmd_line: String,
+    // for random starting configs
+    pub seed: Option<u64>,
+}
+
+impl RunInfo {
+    pub fn to_ca(&self, radix: u32) -> CA {
+        CA::new(self.nabor_size, radix, self.rule.clone(), self.border)
+    }
+}
+
+impl Default for RunInfo {
+    fn default() -> Self {
+        Self {
+            nabor_size: 3,
+            rule: CAEvalType::Rule(BigUint::from(0u32)),
+            border: Border::Ring,
+            cmd_line: String::new(),
+            seed: None,
+        }
+    }
+}
+
+// build one of the built in writers for the given output type, writing to w
+pub fn get_writer<W: Write + 'static>(
+    o: Output,
+    w: W,
+    radix: u32,
+    width: usize,
+    hite: usize,
+    opts: &WriterOpts,
+) -> io::Result<Box<dyn CAWriter>> {
+    // still images need every generation before they can be turned on their
+    // side, GIF frames are small enough to do it as it goes
+    if opts.transpose && matches!(o, Output::PNG | Output::SVG | Output::Sixel) {
+        let upright = WriterOpts {
+            transpose: false,
+            ..opts.clone()
+        };
+        let inner = get_writer(o, w, radix, hite, width, &upright)?;
+        return Ok(Box::new(TransposeWriter::new(inner, width, hite)));
+    }
+
+    Ok(match o {
+        Output::Null => Box::new(NullWriter::new()),
+        Output::Cell => Box::new(CellWriter::new(w, width)),
+        Output::Ascii => Box::new(AsciiWriter::new(w, radix, width)),
+        Output::Unicode => Box::new(UnicodeWriter::new(w, radix, width)),
+        Output::AnsiGrey if opts.color == ColorDepth::NoColor && Output::Unicode.supports(radix) => {
+            Box::new(UnicodeWriter::new(w, radix, width))
+        }
+        Output::AnsiGrey if opts.color == ColorDepth::NoColor => Box::new(CellWriter::new(w, width)),
+        Output::AnsiGrey => Box::new(AnsiGreyWriter::new(w, radix, opts.color)),
+        Output::UnicodeAnsi if opts.color == ColorDepth::NoColor => Box::new(GlyphWriter::half(w, radix, width)),
+        Output::UnicodeAnsi => Box::new(UnicodeAnsiWriter::new(w, &opts.palette.io_colors(radix)?, opts.color)),
+        Output::PNG => Box::new(PNGWriter::new(w, radix, width, hite, opts)?),
+        Output::Raw => Box::new(RawWriter::new(w, radix, width, hite, opts)?),
+        Output::SVG => Box::new(SVGWriter::new(w, radix, width, hite, opts)?),
+        Output::GIF => Box::new(GIFWriter::new(w, radix, width, opts)?),
+        Output::Sixel => Box::new(SixelWriter::new(w, radix, width, hite, opts)?),
+        Output::Braille => Box::new(GlyphWriter::braille(w, radix, width)),
+        Output::Quadrant => Box::new(GlyphWriter::quadrant(w, radix, width)),
+        Output::Npy => Box::new(NpyWriter::new(w, width, hite)?),
+        Output::Json => Box::new(JsonWriter::new(w, radix, width, hite, opts)?),
+        Output::Csv => Box::new(CsvWriter::new(w, radix, width, opts)?),
+        Output::Html => Box::new(HtmlWriter::new(w, radix, width, hite, opts)?),
+    })
+}
+
+// same names FromStr takes
+impl fmt::Display for Output {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
     }
 }
 
@@ -530,11 +792,158 @@ impl FromStr for Output {
             "Cell" => Ok(Output::Cell),
             "PNG" => Ok(Output::PNG),
             "Raw" => Ok(Output::Raw),
+            "SVG" => Ok(Output::SVG),
+            "GIF" => Ok(Output::GIF),
+            "Sixel" => Ok(Output::Sixel),
+            "Braille" => Ok(Output::Braille),
+            "Quadrant" => Ok(Output::Quadrant),
+            "Npy" => Ok(Output::Npy),
+            "Json" => Ok(Output::Json),
+            "Csv" => Ok(Output::Csv),
+            "Html" => Ok(Output::Html),
             _ => Err("invalid output type"),
         }
     }
 }
 
+// an output type plus where it goes: "PNG:run.png", stdout if no path or "-"
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct OutputSpec {
+    pub output: Output,
+    pub path: Option<PathBuf>,
+}
+
+impl OutputSpec {
+    pub fn is_stdout(&self) -> bool {
+        self.path.is_none()
+    }
+
+    pub fn writer(&self, radix: u32, width: usize, hite: usize, opts: &WriterOpts) -> io::Result<Box<dyn CAWriter>> {
+        match &self.path {
+            None => get_writer(self.output, io::stdout(), radix, width, hite, opts),
+            Some(p) => {
+                let f = BufWriter::new(File::create(p)?);
+                get_writer(self.output, f, radix, width, hite, opts)
+            }
+        }
+    }
+}
+
+impl FromStr for OutputSpec {
+    type Err = &'static str;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let (output, path) = match input.split_once(':') {
+            None | Some((_, "-")) => (input.split(':').next().unwrap(), None),
+            Some((_, "")) => return Err("empty output path"),
+            Some((o, p)) => (o, Some(PathBuf::from(p))),
+        };
+
+        Ok(OutputSpec {
+            output: output.parse()?,
+            path,
+        })
+    }
+}
+
+impl fmt::Display for OutputSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.path {
+            None => write!(f, "{}", self.output),
+            Some(p) => write!(f, "{}:{}", self.output, p.display()),
+        }
+    }
+}
+
+// Holds on to the whole run and then feeds it to inner on its side, one line
+// per cell position. inner must have been made for a hite x width image
+pub struct TransposeWriter {
+    inner: Box<dyn CAWriter>,
+    width: usize,
+    hite: usize,
+    cells: Vec<Cell>,
+}
+
+impl TransposeWriter {
+    pub fn new(inner: Box<dyn CAWriter>, width: usize, hite: usize) -> Self {
+        Self {
+            inner,
+            width,
+            hite,
+            cells: Vec::with_capacity(width * hite),
+        }
+    }
+}
+
+impl CAWriter for TransposeWriter {
+    fn write_line(&mut self, v: &Lattice) -> io::Result<()> {
+        self.cells.extend_from_slice(v);
+        Ok(())
+    }
+
+    // a short run is padded out with CELL0 to the promised hite
+    fn finish(&mut self) -> io::Result<()> {
+        let rows = self.cells.len() / self.width;
+        let mut col = vec![CELL0; self.hite];
+
+        for x in 0..self.width {
+            for (y, c) in col.iter_mut().enumerate().take(rows) {
+                *c = self.cells[y * self.width + x];
+            }
+            self.inner.write_line(&col)?;
+        }
+
+        self.inner.finish()
+    }
+}
+
+// fans each generation out to several writers.
+// a writer whose reader went away is dropped, the rest carry on
+pub struct TeeWriter {
+    writers: Vec<Box<dyn CAWriter>>,
+}
+
+impl TeeWriter {
+    pub fn new(writers: Vec<Box<dyn CAWriter>>) -> Self {
+        Self { writers }
+    }
+}
+
+impl CAWriter for TeeWriter {
+    fn write_line(&mut self, v: &Lattice) -> io::Result<()> {
+        let mut i = 0;
+
+        while i < self.writers.len() {
+            match self.writers[i].write_line(v) {
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {
+                    self.writers.remove(i);
+                    if self.writers.is_empty() {
+                        return Err(e);
+                    }
+                }
+                Err(e) => return Err(e),
+                Ok(()) => i += 1,
+            }
+        }
+
+        Ok(())
+    }
+
+    // finish everything even if one fails, report the first error
+    fn finish(&mut self) -> io::Result<()> {
+        let mut result = Ok(());
+
+        for w in self.writers.iter_mut() {
+            let r = w.finish();
+            if result.is_ok() {
+                result = r;
+            }
+        }
+
+        result
+    }
+}
+
 // TODO: specify the fixed border symbol(s?)
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub enum Border {
@@ -554,6 +963,15 @@ impl FromStr for Border {
     }
 }
 
+impl fmt::Display for Border {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Border::Ring => write!(f, "ring"),
+            Border::Fixed => write!(f, "fixed"),
+        }
+    }
+}
+
 pub struct CA {
     nabor_size: u32,
     rule_order: u32,
@@ -585,6 +1003,15 @@ impl CA {
         }
     }
  
+    pub fn run_info(&self) -> RunInfo {
+        RunInfo {
+            nabor_size: self.nabor_size,
+            rule: self.rule.eval_type.clone(),
+            border: self.border,
+            ..RunInfo::default()
+        }
+    }
+
     // given a singed integer and an array length, treat the integer as
     // an index and "roll" it over the array
     // ie modulo with defined behavior for negative numbers
@@ -602,94 +1029,224 @@ impl CA {
     }
 
     // fixed border of lowest symbol
-    fn eval_fixed(&self, config: &Lattice) -> Vec<u8> {
-        let mut next: Vec<u8> = Vec::with_capacity(config.len());
-        let nabor_size = self.nabor_size as usize;
-        let nabor_side = (nabor_size - 1) / 2;
-        let mut nabors: Vec<u8> = Vec::with_capacity(nabor_size);
+    fn step_fixed(&self, src: &[Cell], dst: &mut [Cell]) {
+        let nabor_side = ((self.nabor_size - 1) / 2) as i32;
 
-        for idx in 0..config.len() {
+        for (idx, d) in dst.iter_mut().enumerate() {
             let idx: i32 = idx as i32;
 
-            for i in (idx - nabor_side as i32)..(idx + nabor_side as i32) + 1 {
-                if i < 0 || i >= config.len() as i32 {
-                    nabors.push(CELL0);
+            let nabors = (idx - nabor_side..=idx + nabor_side).map(|i| {
+                if i < 0 || i >= src.len() as i32 {
+                    CELL0
                 } else {
-                    nabors.push(config[i as usize]);
+                    src[i as usize]
                 }
-            }
+            });
 
-            next.push(self.rule.eval(&nabors));
-            nabors.clear();
+            *d = self.rule.eval(nabors);
         }
-
-        next
     }
 
-    fn eval_ring(&self, config: &Lattice) -> Lattice {
-        let mut next: Vec<u8> = Vec::with_capacity(config.len());
-        let nabor_size = self.nabor_size as usize;
-        let nabor_side = (nabor_size - 1) / 2;
-        let mut nabors: Lattice = Vec::with_capacity(nabor_size);
+    fn step_ring(&self, src: &[Cell], dst: &mut [Cell]) {
+        let nabor_side = ((self.nabor_size - 1) / 2) as i32;
 
-        for idx in 0..config.len() {
+        for (idx, d) in dst.iter_mut().enumerate() {
             let idx: i32 = idx as i32;
 
-            for i in (idx - nabor_side as i32)..(idx + nabor_side as i32) + 1 {
-                nabors.push(config[Self::idx_mod(i, config.len())]);
-            }
+            let nabors = (idx - nabor_side..=idx + nabor_side).map(|i| src[Self::idx_mod(i, src.len())]);
 
-            next.push(self.rule.eval(&nabors));
-            nabors.clear();
+            *d = self.rule.eval(nabors);
         }
+    }
 
-        next
+    // compute the generation after src into dst, which must be the same length.
+    // allocates nothing, so swap two buffers back and forth (see Stepper)
+    pub fn step_into(&self, src: &[Cell], dst: &mut [Cell]) {
+        assert_eq!(src.len(), dst.len());
+
+        match self.border {
+            Border::Ring => self.step_ring(src, dst),
+            Border::Fixed => self.step_fixed(src, dst),
+        }
     }
 
     pub fn gtf(&self, config: &Lattice) -> Lattice {
-        match self.border {
-            Border::Ring => self.eval_ring(config),
-            Border::Fixed => self.eval_fixed(config),
+        let mut next = vec![CELL0; config.len()];
+        self.step_into(config, &mut next);
+        next
+    }
+
+    // double buffered stepping from start, the borrowing cousin of iter()
+    pub fn stepper(&self, start: Lattice) -> Stepper<'_> {
+        Stepper {
+            ca: self,
+            next: vec![CELL0; start.len()],
+            config: start,
         }
     }
+
+    // every generation starting with (and including) start, never ends
+    // so bound it with take() and friends
+    pub fn iter(&self, start: Lattice) -> Generations<'_> {
+        Generations {
+            ca: self,
+            config: start,
+        }
+    }
+}
+
+pub struct Generations<'a> {
+    ca: &'a CA,
+    config: Lattice,
+}
+
+impl Iterator for Generations<'_> {
+    type Item = Lattice;
+
+    fn next(&mut self) -> Option<Lattice> {
+        let next = self.ca.gtf(&self.config);
+        Some(std::mem::replace(&mut self.config, next))
+    }
+}
+
+pub struct Stepper<'a> {
+    ca: &'a CA,
+    config: Lattice,
+    next: Lattice,
+}
+
+impl Stepper<'_> {
+    pub fn current(&self) -> &Lattice {
+        &self.config
+    }
+
+    pub fn step(&mut self) {
+        self.ca.step_into(&self.config, &mut self.next);
+        std::mem::swap(&mut self.config, &mut self.next);
+    }
+
+    pub fn into_current(self) -> Lattice {
+        self.config
+    }
 }
 
 pub struct CAPrinter<'a> {
-    output: Box<dyn CAWriter>,
+    output: &'a mut dyn CAWriter,
     ca: &'a CA,
 }
 
-impl CAPrinter<'_> {
-    pub fn new(output: Output, ca: & CA, width: usize, hite: usize) -> CAPrinter<'_> {
+impl<'a> CAPrinter<'a> {
+    pub fn new(output: &'a mut dyn CAWriter, ca: &'a CA) -> CAPrinter<'a> {
         CAPrinter {
-            output: get_printer(output, ca.rule_order, width, hite),
+            output,
             ca,
         }
     }
 
     // returns cells per second
-    fn eval(&mut self, from: usize, count: usize, config: & Lattice) -> (f64, Lattice) {
-        let mut config = config.clone();
+    fn eval(&mut self, from: usize, count: usize, config: & Lattice) -> io::Result<(f64, Lattice)> {
+        let mut stepper = self.ca.stepper(config.clone());
+        let mut written = 0;
+        let start = Instant::now();
+
+        for _ in 0..from {
+            stepper.step();
+        }
+
+        let result = self
+            .write(&mut stepper, count, &mut written)
+            .and_then(|_| self.output.finish());
+        ignore_broken_pipe(result)?;
+
+        Ok((
+            ((from + written) * config.len()) as f64 / start.elapsed().as_secs_f64(),
+            stepper.into_current(),
+        ))
+    }
+
+    // no count, goes until stop is set with a generation every `every`
+    fn follow(&mut self, from: usize, every: Duration, config: &Lattice, stop: &AtomicBool) -> io::Result<(f64, Lattice)> {
+        let mut stepper = self.ca.stepper(config.clone());
+        let mut written = 0;
         let start = Instant::now();
 
         for _ in 0..from {
-            config = self.ca.gtf(&config);
+            stepper.step();
         }
 
+        let result = self
+            .pace(&mut stepper, every, stop, &mut written)
+            .and_then(|_| self.output.finish());
+        ignore_broken_pipe(result)?;
+
+        Ok((
+            ((from + written) * config.len()) as f64 / start.elapsed().as_secs_f64(),
+            stepper.into_current(),
+        ))
+    }
+
+    fn pace(&mut self, stepper: &mut Stepper, every: Duration, stop: &AtomicBool, written: &mut usize) -> io::Result<()> {
+        let mut next = Instant::now();
+
+        while !stop.load(Ordering::Relaxed) {
+            self.output.write_line(stepper.current())?;
+            stepper.step();
+            *written += 1;
+
+            // catch up rather than sleep when running behind
+            next += every;
+            let now = Instant::now();
+            if next > now {
+                std::thread::sleep(next - now);
+            } else {
+                next = now;
+            }
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, stepper: &mut Stepper, count: usize, written: &mut usize) -> io::Result<()> {
         for _ in 0..count {
-            self.output.write_line(&config);
-            config = self.ca.gtf(&config);
+            self.output.write_line(stepper.current())?;
+            stepper.step();
+            *written += 1;
         }
+        Ok(())
+    }
+}
 
-        (
-            ((from + count) * config.len()) as f64 / start.elapsed().as_secs_f64(),
-            config,
-        )
+// the reader going away (eg `ca1d ... | head`) is a normal way to stop
+fn ignore_broken_pipe(result: io::Result<()>) -> io::Result<()> {
+    match result {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        r => r,
     }
 }
 
-pub fn automate(output: Output, from: usize, to: usize, ca: &CA, start_config: & Lattice) -> (f64, Lattice) {
+pub fn automate(output: Output, from: usize, to: usize, ca: &CA, start_config: & Lattice) -> io::Result<(f64, Lattice)> {
     let width = start_config.len();
-    let mut output = CAPrinter::new(output, ca, width, to);
-    output.eval(from, to, start_config)
+    let opts = WriterOpts {
+        run: ca.run_info(),
+        ..WriterOpts::default()
+    };
+    let mut writer = get_writer(output, io::stdout(), ca.rule_order, width, to, &opts)?;
+    automate_with(writer.as_mut(), from, to, ca, start_config)
+}
+
+// same as automate() but with a caller supplied writer
+pub fn automate_with(writer: &mut dyn CAWriter, from: usize, to: usize, ca: &CA, start_config: & Lattice) -> io::Result<(f64, Lattice)> {
+    CAPrinter::new(writer, ca).eval(from, to, start_config)
+}
+
+// automate_with() without an end: writes a generation every `every` until
+// stop is set (eg from a signal handler) or the reader goes away
+pub fn follow_with(
+    writer: &mut dyn CAWriter,
+    from: usize,
+    every: Duration,
+    ca: &CA,
+    start_config: &Lattice,
+    stop: &AtomicBool,
+) -> io::Result<(f64, Lattice)> {
+    CAPrinter::new(writer, ca).follow(from, every, start_config, stop)
 }
@@ -0,0 +1,106 @@
+// Starting configs from the output of an earlier run. Multi-row inputs give
+// their last row, so a run can carry on where another stopped
+use std::io::{self, Cursor, Read};
+
+use crate::raw::{MAGIC, VERSION};
+use crate::{from_char, Cell, Lattice, PaletteSpec, RawReader, Rgb};
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+fn invalid(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+// Reads a Raw file, a PNG or text with a row of base 36 digits per line.
+// PNG colors are mapped to the nearest palette color, scale is what the image
+// was drawn at (grid lines are fine)
+pub fn read_config<R: Read>(mut r: R, radix: u32, palette: &PaletteSpec, scale: usize) -> io::Result<Lattice> {
+    let mut data = Vec::new();
+    r.read_to_end(&mut data)?;
+
+    // CA1D is base 36 digits too, so text is anything without a whole header
+    let reader = if data.starts_with(MAGIC) && data.get(MAGIC.len()) == Some(&VERSION) {
+        RawReader::new(data.as_slice()).ok()
+    } else {
+        None
+    };
+
+    let config = if let Some(reader) = reader {
+        last_raw_row(reader)?
+    } else if data.starts_with(PNG_SIGNATURE) {
+        let colors = palette
+            .colors(radix)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        last_png_row(&data, &colors, scale.max(1))?
+    } else {
+        last_text_row(&data, radix)?
+    };
+
+    if config.is_empty() {
+        return Err(invalid("config is empty"));
+    }
+    if config.iter().any(|c| *c as u32 >= radix) {
+        return Err(invalid("config has symbols outside the radix"));
+    }
+    Ok(config)
+}
+
+fn last_raw_row(reader: RawReader<&[u8]>) -> io::Result<Lattice> {
+    let mut last = None;
+    for row in reader {
+        last = Some(row?);
+    }
+    last.ok_or_else(|| invalid("raw file has no generations"))
+}
+
+fn last_text_row(data: &[u8], radix: u32) -> io::Result<Lattice> {
+    let text = std::str::from_utf8(data).map_err(|_| invalid("config is not a raw file, PNG or text"))?;
+    let line = text
+        .lines()
+        .map(str::trim)
+        .rfind(|l| !l.is_empty())
+        .ok_or_else(|| invalid("config is empty"))?;
+
+    if !line.chars().all(|c| c.is_ascii_alphanumeric() && (from_char(c) as u32) < radix) {
+        return Err(invalid("invalid character in config"));
+    }
+    Ok(line.chars().map(from_char).collect())
+}
+
+fn last_png_row(data: &[u8], colors: &[Rgb], scale: usize) -> io::Result<Lattice> {
+    let mut decoder = png::Decoder::new(Cursor::new(data));
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    let mut buf = vec![0; reader.output_buffer_size().ok_or_else(|| invalid("PNG too large"))?];
+    let frame = reader.next_frame(&mut buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    let (width, hite) = (frame.width as usize, frame.height as usize);
+    if width < scale || hite < scale {
+        return Err(invalid("PNG smaller than one cell"));
+    }
+
+    let samples = frame.color_type.samples();
+    // the top left pixel of each cell in the last row, clear of any grid
+    let y = hite - scale;
+    let row = &buf[y * frame.line_size..(y + 1) * frame.line_size];
+
+    Ok((0..width / scale)
+        .map(|x| {
+            let px = &row[x * scale * samples..];
+            let rgb = match samples {
+                1 | 2 => (px[0], px[0], px[0]),
+                _ => (px[0], px[1], px[2]),
+            };
+            nearest(rgb, colors)
+        })
+        .collect())
+}
+
+fn nearest((r, g, b): Rgb, colors: &[Rgb]) -> Cell {
+    let dist = |(cr, cg, cb): Rgb| {
+        let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+        d(r, cr) + d(g, cg) + d(b, cb)
+    };
+    (0..colors.len()).min_by_key(|i| dist(colors[*i])).unwrap_or(0) as Cell
+}
@@ -1,11 +1,24 @@
+use std::fs::File;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use std::str::FromStr;
+
 use clap::Parser;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 
-use rand::Rng;
+use ca1d::{automate_with, follow_with, Border, CAEvalType, CAWriter, ColorDepth, ColorWhen, Lattice, Output, OutputSpec, PaletteSpec, RunInfo, TeeWriter, WriterOpts, CA, ConfigPattern, from_digit, read_config};
 
-use ca1d::{automate, Border, CAEvalType, Cell, Lattice, Output, CA, CELL0, from_char};
+mod commands;
+mod tui;
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 #[clap(version = "1.0", author = "www.github.com/pmmccorm/ca1d")]
+#[clap(after_help = "`ca1d icon --help` for drawing a rule's table, `ca1d run --help` for run files")]
 struct Opts {
     /// number of symbols (1, 36]
     radix: u32,
@@ -16,9 +29,34 @@ struct Opts {
     /// Wolfram style rule number [0, radix^radix^neighbor_size)
     rule_number: CAEvalType,
 
-    /// initial configuration string in base 36, eg "01f" -> 0, 1, 15
+    /// initial configuration in base 36, clauses separated by ';'.
+    /// Not needed with --config-file
+    ///   01f         digits 0, 1, 15, centered
+    ///   (01)*4      a group 4 times, (01)* repeats it to fill the width
+    ///   @  @0.3     random cells (30% not 0), @*20 for just 20 of them
+    ///   5:1,20:12   cells from the left: 5 is 1, 20 and 21 are 1 and 2
+    ///   bg=01       background under everything instead of 0s
+    ///   align=left  left, center or right
+    ///   overflow=wrap  past the width: error, truncate or wrap around
+    #[clap(default_value(""), hide_default_value(true), verbatim_doc_comment)]
     start_config: String,
 
+    /// start from the last row of an earlier run's Raw, PNG or Cell output
+    /// (or any text of base 36 digits), - reads stdin. PNGs are read back with
+    /// --palette and --scale
+    #[clap(long)]
+    config_file: Option<PathBuf>,
+
+    /// seed for random (@) start configs, so a run can be repeated. One is
+    /// picked and shown in -v output when not given
+    #[clap(long)]
+    seed: Option<u64>,
+
+    /// odds of random (@) cells: the chance of not being 0 (eg 0.1), or a
+    /// weight for each symbol (eg 8,1,1)
+    #[clap(long, value_delimiter(','))]
+    density: Vec<f64>,
+
     /// neighbor mask NNN.. with N being 0|1, and count of N matching nabor_size
     /// By default all neighbors are evaluated.
     #[clap(short, long, default_value("0"))]
@@ -28,9 +66,50 @@ struct Opts {
     #[clap(short, long, default_value("0"))]
     verbose: i32,
 
-    /// select output type
+    /// select output type, TYPE or TYPE:PATH to write to a file.
+    /// May be repeated to write several outputs from the same run
     #[clap(short, long, default_value("UnicodeAnsi"))]
-    output: Output,
+    output: Vec<OutputSpec>,
+
+    /// size of a cell in image outputs (PNG, SVG, GIF, Sixel)
+    #[clap(long, default_value("1"))]
+    scale: usize,
+
+    /// draw grid lines between cells in image outputs, needs --scale 2 or more
+    /// except for SVG
+    #[clap(long)]
+    grid: bool,
+
+    /// colors for UnicodeAnsi and image outputs: classic, greyscale, viridis,
+    /// magma, cubehelix, categorical, gradient:RRGGBB,RRGGBB[,...] to spread
+    /// symbols over a gradient, or RRGGBB,RRGGBB,... giving each symbol's color
+    #[clap(long, default_value("classic"))]
+    palette: PaletteSpec,
+
+    /// terminal colors: auto, always or never. auto goes by NO_COLOR, COLORTERM,
+    /// TERM and whether stdout is a terminal
+    #[clap(long, default_value("auto"))]
+    color: ColorWhen,
+
+    /// image outputs run time left to right instead of top to bottom
+    #[clap(long)]
+    transpose: bool,
+
+    /// GIF: milliseconds between frames (10ms resolution)
+    #[clap(long, default_value("100"))]
+    delay: u32,
+
+    /// GIF: times to play the animation, 0 loops forever
+    #[clap(long, default_value("0"))]
+    loops: u16,
+
+    /// GIF: generations per frame, more than 1 scrolls the automata through the frame
+    #[clap(long, default_value("1"))]
+    window: usize,
+
+    /// Raw: pack cells into 1, 2 or 4 bits for radix up to 16
+    #[clap(long)]
+    pack: bool,
 
     /// border behavior: ring or fixed
     #[clap(short, long, default_value("ring"))]
@@ -40,13 +119,31 @@ struct Opts {
     #[clap(long, default_value("0"))]
     width: usize,
 
-    /// length of automata: N or 0 will choose terminal heigth
+    /// length of automata: N generations, or 0 to fill the terminal height
+    /// (several generations a line for UnicodeAnsi, Braille and Quadrant)
     #[clap(long, default_value("0"))]
     to: usize,
 
     /// start displaying automation after N steps
     #[clap(long, default_value("0"))]
     from: usize,
+
+    /// keep going until interrupted instead of stopping after --to generations
+    #[clap(long)]
+    follow: bool,
+
+    /// with --follow, terminal lines per second, 0 goes as fast as it can
+    #[clap(long, default_value("10"))]
+    fps: f64,
+
+    /// explore full screen: keys change the rule, config, border and output
+    /// and scroll through time. Prints the matching command line on exit
+    #[clap(short, long)]
+    interactive: bool,
+
+    /// also write the run's options to a TOML run file, for `ca1d run`
+    #[clap(long)]
+    save: Option<PathBuf>,
 }
 
 impl Opts {
@@ -75,63 +172,162 @@ impl Opts {
             return false;
         }
 
+        if let Err(e) = self.palette.colors(self.radix) {
+            eprintln!("{}", e);
+            return false;
+        }
+
+        if self.scale == 0 {
+            eprintln!("scale must be at least 1");
+            return false;
+        }
+
+        if !(self.fps >= 0.0 && self.fps.is_finite()) {
+            eprintln!("fps must be 0 or more");
+            return false;
+        }
+
+        // these need to know how long the run is before they start
+        if self.follow && self.output.iter().any(|o| matches!(o.output, Output::PNG | Output::SVG | Output::Sixel | Output::Npy | Output::Html)) {
+            eprintln!("--follow can't write PNG, SVG, Sixel, Npy or Html");
+            return false;
+        }
+
+        if self.output.iter().filter(|o| o.is_stdout()).count() > 1 {
+            eprintln!("only one output can go to stdout");
+            return false;
+        }
+
         // we don't validate if rule is too larger here
         // just silently use lower/needed bits
 
-        // @|[0..radix]
-        // TODO
-        if self.start_config == "@" {
-        } else {
-            for c in self.start_config.chars() {
-                if from_char(c) >= self.radix as u8 {
-                    eprintln!("invalid character in given config: {}", c);
-                    return false;
-                }
+        if self.start_config.is_empty() == self.config_file.is_none() {
+            eprintln!("need a start config or --config-file, but not both");
+            return false;
+        }
+
+        if self.config_file.is_none() {
+            if let Err(e) = self.pattern() {
+                eprintln!("{}", e);
+                return false;
             }
         }
 
         true
     }
 
+    // the output on stdout (if any) is the one sized to the terminal
+    fn term_output(&self) -> Option<Output> {
+        self.output.iter().find(|o| o.is_stdout()).map(|o| o.output)
+    }
+
+    fn packing(&self) -> (usize, usize) {
+        self.term_output().map_or((1, 1), |o| o.packing())
+    }
+
+    // --to is in generations, filling the terminal can take several a line
     fn hite(&self) -> usize {
-        if self.output == Output::UnicodeAnsi {
-            return 2 * term_hite(self.to);
+        if self.to > 0 {
+            return self.to;
         }
-        term_hite(self.to)
+        self.packing().1 * term_hite(0)
     }
 
+    // terminal width is in characters, which may hold several cells
     fn width(&self) -> usize {
-        term_width(self.width)
+        if self.width == 0 {
+            return self.packing().0 * term_width(0);
+        }
+        self.width
     }
 
-    // @ means all random config
-    // TODO: aborts here if input str has chars not in radix
-    fn config(&self) -> Lattice {
-        let width = self.width();
-        let mut config = Lattice::with_capacity(width);
+    fn pattern(&self) -> Result<ConfigPattern, &'static str> {
+        ConfigPattern::new(&self.start_config, self.radix)?.density(&self.density, self.radix)
+    }
 
-        if self.start_config == "@" {
-            let mut rng = rand::rng();
-            for _ in 0..width {
-                config.push(rng.random_range(CELL0..self.radix as Cell));
-            }
-        } else {
-            // normal fill logic
-            let padding = (width - self.start_config.len()) / 2;
-            let mut lpad = vec![CELL0; padding];
-            let mut rpad = vec![CELL0; padding];
+    // random starts get a seed, so the run can be repeated
+    fn pick_seed(&mut self) {
+        if self.seed.is_none() && self.pattern().is_ok_and(|p| p.is_random()) {
+            self.seed = Some(rand::rng().random());
+        }
+    }
 
-            config.append(&mut lpad);
+    // the start config pattern laid out across the width. ChaCha8 gives the
+    // same cells for a seed on every platform and rand version, unlike StdRng
+    fn config(&self) -> Result<Lattice, &'static str> {
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed.unwrap_or(0));
+        self.pattern()?.build(self.width(), self.radix, &mut rng)
+    }
 
-            for c in self.start_config.chars() {
-                let d = c.to_digit(self.radix).unwrap();
-                config.push(d as Cell);
-            }
+    // color is up to where the output goes
+    fn writer_opts(&self) -> WriterOpts {
+        WriterOpts {
+            scale: self.scale,
+            grid: self.grid,
+            delay: u16::try_from(self.delay / 10).unwrap_or(u16::MAX),
+            loops: self.loops,
+            window: self.window,
+            transpose: self.transpose,
+            palette: self.palette.clone(),
+            color: ColorDepth::NoColor,
+            pack: self.pack,
+            run: RunInfo {
+                cmd_line: cmd_line(self),
+                seed: self.seed,
+                ..self.to_ca().run_info()
+            },
+        }
+    }
+
+    fn writer(&self, width: usize, hite: usize) -> std::io::Result<Box<dyn CAWriter>> {
+        let mut writers = Vec::new();
+        let wopts = self.writer_opts();
+
+        for o in &self.output {
+            let tty = o.is_stdout() && std::io::stdout().is_terminal();
+            let wopts = WriterOpts {
+                color: self.color.depth(tty),
+                ..wopts.clone()
+            };
+            writers.push(o.writer(self.radix, width, hite, &wopts)?);
+        }
 
-            config.append(&mut rpad);
+        if writers.len() == 1 {
+            return Ok(writers.pop().unwrap());
         }
 
-        config
+        Ok(Box::new(TeeWriter::new(writers)))
+    }
+
+    // --fps counts terminal lines, which can hold several generations
+    fn every(&self) -> Duration {
+        if self.fps == 0.0 {
+            return Duration::ZERO;
+        }
+        Duration::from_secs_f64(1.0 / (self.fps * self.packing().1 as f64))
+    }
+
+    // turns --config-file into a start config, sized to the file unless
+    // --width says otherwise
+    fn load_config(&mut self) -> Result<(), String> {
+        let Some(path) = &self.config_file else {
+            return Ok(());
+        };
+
+        let config = if path.as_os_str() == "-" {
+            read_config(std::io::stdin().lock(), self.radix, &self.palette, self.scale)
+        } else {
+            File::open(path).and_then(|f| read_config(f, self.radix, &self.palette, self.scale))
+        };
+        let config = config.map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        if self.width == 0 {
+            self.width = config.len();
+        } else if config.len() > self.width {
+            return Err(format!("{} is wider than --width", path.display()));
+        }
+        self.start_config = config.iter().map(from_digit).collect();
+        Ok(())
     }
 
     // versus implenenting From trait
@@ -173,29 +369,156 @@ fn term_width(from_opts: usize) -> usize {
     }
 }
 
-fn cmd_line(opts: Opts) -> String {
-    format!("ca1d {} {} {} {}", opts.radix, opts.nabor_size, opts.rule_number, opts.start_config)
+fn cmd_line(opts: &Opts) -> String {
+    // patterns can have characters the shell would take
+    let config = if opts.start_config.chars().all(|c| c.is_ascii_alphanumeric() || c == '@' || c == '.') {
+        opts.start_config.clone()
+    } else {
+        format!("'{}'", opts.start_config)
+    };
+    let mut line = format!("ca1d {} {} {} {}", opts.radix, opts.nabor_size, opts.rule_number, config);
+
+    if let Some(seed) = opts.seed {
+        line += &format!(" --seed {}", seed);
+    }
+    if !opts.density.is_empty() {
+        let density: Vec<String> = opts.density.iter().map(f64::to_string).collect();
+        line += &format!(" --density {}", density.join(","));
+    }
+    if opts.border != Border::Ring {
+        line += &format!(" --border {}", opts.border);
+    }
+    if opts.output != [OutputSpec::from_str("UnicodeAnsi").unwrap()] {
+        for o in &opts.output {
+            line += &format!(" --output {}", o);
+        }
+    }
+    // a random start only comes back the same at the same width
+    let width = if opts.width == 0 && opts.pattern().is_ok_and(|p| p.is_random()) {
+        opts.width()
+    } else {
+        opts.width
+    };
+    if width > 0 {
+        line += &format!(" --width {}", width);
+    }
+    if opts.to > 0 {
+        line += &format!(" --to {}", opts.to);
+    }
+    if opts.from > 0 {
+        line += &format!(" --from {}", opts.from);
+    }
+
+    line
 }
 
 pub fn main() {
-    let opts: Opts = Opts::parse();
+    let args: Vec<String> = std::env::args().collect();
+
+    if args.get(1).map(String::as_str) == Some("icon") {
+        if let Err(e) = commands::icon(&args[1..]) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // a run file is turned back into the command line it stands for
+    let mut opts: Opts = if args.get(1).map(String::as_str) == Some("run") {
+        match commands::run_args(&args[1..]) {
+            Ok(args) => Opts::parse_from(args),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        Opts::parse_from(&args)
+    };
 
     if !opts.validate_opts() {
         println!("invalid options");
         return;
     }
 
+    if let Err(e) = opts.load_config() {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+    opts.pick_seed();
+
+    if let Some(path) = &opts.save {
+        if let Err(e) = commands::save(&opts, path) {
+            eprintln!("{}: {}", path.display(), e);
+            std::process::exit(1);
+        }
+    }
+
+    if opts.interactive {
+        if !std::io::stdout().is_terminal() {
+            eprintln!("--interactive needs a terminal");
+            std::process::exit(1);
+        }
+        match tui::run(&opts) {
+            Ok(last) => println!("{}", cmd_line(&last)),
+            Err(e) => {
+                eprintln!("error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if opts.verbose > 0 {
+        if let Some(seed) = opts.seed {
+            eprintln!("seed {}", seed);
+        }
+    }
+
     let ca = opts.to_ca();
+    let config = match opts.config() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    // a followed run has no length to give the writers
+    let hite = if opts.follow { 0 } else { opts.hite() };
+
+    // ^C stops a --follow run between generations, so the writer can finish
+    // (and put the terminal colors back) like any other run
+    let stop = Arc::new(AtomicBool::new(false));
+    if opts.follow {
+        let stop = stop.clone();
+        if let Err(e) = ctrlc::set_handler(move || stop.store(true, Ordering::Relaxed)) {
+            eprintln!("can't catch interrupts: {}", e);
+            std::process::exit(1);
+        }
+    }
 
-    let (per_s, final_config) = automate(opts.output,
-                                         opts.from,
-                                         opts.hite(),
-                                         &ca,
-                                         &opts.config());
+    let result = opts.writer(config.len(), hite).and_then(|mut w| {
+        if opts.follow {
+            follow_with(w.as_mut(), opts.from, opts.every(), &ca, &config, &stop)
+        } else {
+            automate_with(w.as_mut(), opts.from, hite, &ca, &config)
+        }
+    });
+
+    let (per_s, final_config) = match result {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("error writing output: {}", e);
+            std::process::exit(1);
+        }
+    };
 
     if opts.verbose > 0 {
         eprintln!("\n{} /s", per_s);
-        eprintln!("{}", cmd_line(opts));
+    }
+
+    if opts.verbose > 0 || opts.follow {
+        eprintln!("{}", cmd_line(&opts));
         eprintln!("{}", CA::print_config(final_config)
         );
     }
@@ -0,0 +1,54 @@
+// NumPy .npy: uint8 array of shape (generations, width), C order.
+// The shape goes in the header before any rows, so the generation count has
+// to be known up front
+use std::io::{self, Write};
+
+use crate::{CAWriter, Lattice};
+
+pub struct NpyWriter<W: Write> {
+    w: W,
+    hite: usize,
+    written: usize,
+}
+impl<W: Write> NpyWriter<W> {
+    pub fn new(mut w: W, width: usize, hite: usize) -> io::Result<Self> {
+        if hite == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Npy needs to know the number of generations"));
+        }
+
+        let mut header = format!(
+            "{{'descr': '|u1', 'fortran_order': False, 'shape': ({}, {}), }}",
+            hite, width
+        );
+        // magic, version and length take 10 bytes, the header is padded
+        // with spaces so the data starts 64 byte aligned, ending in \n
+        let total = (10 + header.len() + 1).next_multiple_of(64);
+        while 10 + header.len() + 1 < total {
+            header.push(' ');
+        }
+        header.push('\n');
+
+        w.write_all(b"\x93NUMPY\x01\x00")?;
+        w.write_all(&(header.len() as u16).to_le_bytes())?;
+        w.write_all(header.as_bytes())?;
+
+        Ok(Self { w, hite, written: 0 })
+    }
+}
+impl<W: Write> CAWriter for NpyWriter<W> {
+    fn write_line(&mut self, v: &Lattice) -> io::Result<()> {
+        self.written += 1;
+        self.w.write_all(v)
+    }
+
+    // a short file would fail to load, say so here instead
+    fn finish(&mut self) -> io::Result<()> {
+        if self.written != self.hite {
+            return Err(io::Error::other(format!(
+                "npy header says {} generations but {} were written",
+                self.hite, self.written
+            )));
+        }
+        self.w.flush()
+    }
+}
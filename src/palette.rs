@@ -0,0 +1,234 @@
+use std::f32::consts::PI;
+use std::io;
+use std::str::FromStr;
+
+use crate::Cell;
+
+pub type Rgb = (u8, u8, u8);
+
+// https://github.com/BIDS/colormap, 10 evenly spaced samples each
+const VIRIDIS: [u32; 10] = [
+    0x440154, 0x482878, 0x3e4989, 0x31688e, 0x26828e, 0x1f9e89, 0x35b779, 0x6ece58, 0xb5de2b, 0xfde725,
+];
+const MAGMA: [u32; 10] = [
+    0x000004, 0x180f3d, 0x440f76, 0x721f81, 0x9e2f7f, 0xcd4071, 0xf1605d, 0xfd9668, 0xfeca8d, 0xfcfdbf,
+];
+// Tableau 10
+const CATEGORICAL: [u32; 10] = [
+    0x4e79a7, 0xf28e2b, 0xe15759, 0x76b7b2, 0x59a14f, 0xedc948, 0xb07aa1, 0xff9da7, 0x9c755f, 0xbab0ac,
+];
+
+fn from_hex(x: u32) -> Rgb {
+    ((x >> 16) as u8, (x >> 8) as u8, x as u8)
+}
+
+fn parse_hex(s: &str) -> Result<Rgb, &'static str> {
+    let s = s.trim().trim_start_matches('#');
+
+    if s.len() != 6 {
+        return Err("colors are given as 6 hex digits, eg ff8000");
+    }
+
+    match u32::from_str_radix(s, 16) {
+        Ok(x) => Ok(from_hex(x)),
+        Err(_) => Err("invalid hex color"),
+    }
+}
+
+fn parse_list(s: &str) -> Result<Vec<Rgb>, &'static str> {
+    s.split(',').map(parse_hex).collect()
+}
+
+fn lerp(a: u8, b: u8, t: f32) -> u8 {
+    (a as f32 + (b as f32 - a as f32) * t).round() as u8
+}
+
+// t in [0,1] along evenly spaced stops
+fn gradient(stops: &[Rgb], t: f32) -> Rgb {
+    let pos = t * (stops.len() - 1) as f32;
+    let i = usize::min(pos as usize, stops.len() - 2);
+    let (a, b) = (stops[i], stops[i + 1]);
+    let t = pos - i as f32;
+
+    (lerp(a.0, b.0, t), lerp(a.1, b.1, t), lerp(a.2, b.2, t))
+}
+
+// D.A. Green's cubehelix, start 0.5, rotations -1.5, hue 1, gamma 1
+fn cubehelix(t: f32) -> Rgb {
+    let phi = 2.0 * PI * (0.5 / 3.0 - 1.5 * t);
+    let amp = t * (1.0 - t) / 2.0;
+    let (cos, sin) = (phi.cos(), phi.sin());
+
+    let channel = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+    (
+        channel(t + amp * (-0.14861 * cos + 1.78277 * sin)),
+        channel(t + amp * (-0.29227 * cos - 0.90649 * sin)),
+        channel(t + amp * (1.97294 * cos)),
+    )
+}
+
+// hue in [0,1)
+fn hsv(h: f32, s: f32, v: f32) -> Rgb {
+    let h = h * 6.0;
+    let f = h - h.floor();
+    let (p, q, t) = (v * (1.0 - s), v * (1.0 - s * f), v * (1.0 - s * (1.0 - f)));
+
+    let (r, g, b) = match h as u32 % 6 {
+        0 => (v, t, p),
+        1 => (q, v, p),
+        2 => (p, v, t),
+        3 => (p, q, v),
+        4 => (t, p, v),
+        _ => (v, p, q),
+    };
+
+    ((r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8)
+}
+
+// Tableau 10 and then golden angle hues, alternating brightness
+fn categorical(c: Cell) -> Rgb {
+    match CATEGORICAL.get(c as usize) {
+        Some(x) => from_hex(*x),
+        None => {
+            let h = (c as f32 * 0.618_034).fract();
+            let v = if c % 2 == 0 { 0.95 } else { 0.7 };
+            hsv(h, 0.65, v)
+        }
+    }
+}
+
+fn to_base_triple(c: Cell, radix: u32) -> Vec<f32> {
+    let mut x: u32 = c.into();
+    let mut result = Vec::new();
+
+    loop {
+        let m = x % radix;
+        x /= radix;
+
+        result.push(m as f32 / (radix - 1) as f32);
+
+        if x == 0 {
+            break;
+        }
+    }
+
+    while result.len() <= 3 {
+        result.push(0.0);
+    }
+
+    result
+}
+
+// covers up to 64 symbols..base 7 will bring to 343
+// convert the given cell [0,255] into a differently based digit that fits into three
+// symbols, ie 10 -> 130 (10 in base 3)
+// then scale the R,G,B values by this triple.
+// makes for maximally distant, but somewhat ugly colors
+fn cell_to_rgb(c: Cell, radix: u32) -> (u8, u8, u8) {
+    assert!(radix <= 64);
+    let base = if radix < 8 {
+        2
+    } else if radix < 27 {
+        3
+    } else {
+        4
+    };
+
+    let t = to_base_triple(c, base);
+
+    (
+        (t[0] * 255.0) as u8,
+        (t[1] * 255.0) as u8,
+        (t[2] * 255.0) as u8,
+    )
+}
+
+// How cells get colored by the color outputs, see --palette
+#[derive(Debug, PartialEq, Clone, Default)]
+pub enum PaletteSpec {
+    // cell_to_rgb
+    #[default]
+    Classic,
+    Greyscale,
+    Viridis,
+    Magma,
+    Cubehelix,
+    Categorical,
+    // evenly spaced stops, cells are spread from the first to the last
+    Gradient(Vec<Rgb>),
+    // one color per symbol
+    List(Vec<Rgb>),
+}
+
+impl PaletteSpec {
+    // a color for every symbol in [0,radix)
+    pub fn colors(&self, radix: u32) -> Result<Vec<Rgb>, &'static str> {
+        let t = |c: Cell| c as f32 / (radix - 1) as f32;
+        let cells = 0..radix as Cell;
+
+        let colors = match self {
+            PaletteSpec::Classic => cells.map(|c| cell_to_rgb(c, radix)).collect(),
+            PaletteSpec::Greyscale => cells.map(|c| gradient(&[(0, 0, 0), (255, 255, 255)], t(c))).collect(),
+            PaletteSpec::Viridis => {
+                let stops: Vec<_> = VIRIDIS.iter().map(|x| from_hex(*x)).collect();
+                cells.map(|c| gradient(&stops, t(c))).collect()
+            }
+            PaletteSpec::Magma => {
+                let stops: Vec<_> = MAGMA.iter().map(|x| from_hex(*x)).collect();
+                cells.map(|c| gradient(&stops, t(c))).collect()
+            }
+            PaletteSpec::Cubehelix => cells.map(|c| cubehelix(t(c))).collect(),
+            PaletteSpec::Categorical => cells.map(categorical).collect(),
+            PaletteSpec::Gradient(stops) => cells.map(|c| gradient(stops, t(c))).collect(),
+            PaletteSpec::List(colors) => {
+                if colors.len() < radix as usize {
+                    return Err("palette has fewer colors than symbols");
+                }
+                colors[..radix as usize].to_vec()
+            }
+        };
+
+        Ok(colors)
+    }
+
+    // colors() as #rrggbb, for SVG and HTML
+    pub(crate) fn css_colors(&self, radix: u32) -> io::Result<Vec<String>> {
+        Ok(self.io_colors(radix)?.iter().map(|c| css(*c)).collect())
+    }
+
+    // colors() for the writers, which only do io::Error
+    pub(crate) fn io_colors(&self, radix: u32) -> io::Result<Vec<Rgb>> {
+        self.colors(radix)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+    }
+}
+
+impl FromStr for PaletteSpec {
+    type Err = &'static str;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "classic" => Ok(PaletteSpec::Classic),
+            "greyscale" | "grey" => Ok(PaletteSpec::Greyscale),
+            "viridis" => Ok(PaletteSpec::Viridis),
+            "magma" => Ok(PaletteSpec::Magma),
+            "cubehelix" => Ok(PaletteSpec::Cubehelix),
+            "categorical" => Ok(PaletteSpec::Categorical),
+            _ => match input.strip_prefix("gradient:") {
+                Some(stops) => {
+                    let stops = parse_list(stops)?;
+                    if stops.len() < 2 {
+                        return Err("a gradient needs at least two colors");
+                    }
+                    Ok(PaletteSpec::Gradient(stops))
+                }
+                None => Ok(PaletteSpec::List(parse_list(input)?)),
+            },
+        }
+    }
+}
+
+pub(crate) fn css((r, g, b): Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
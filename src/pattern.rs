@@ -0,0 +1,396 @@
+// Starting config patterns: clauses separated by ';'
+//
+//   01f         digits, placed in the lattice per align
+//   (01)*4      a group repeated 4 times, (01)* repeats it to fill the width
+//   @ @0.3      random cells, optionally the fraction of them that aren't 0.
+//               Alone they fill the width, @*20 or @0.3*20 is 20 cells
+//   5:1,20:12   cell 5 is 1 and cells 20 and 21 are 1 and 2, from the left
+//   bg=01       a background repeated under everything instead of 0s
+//   align=left  where the digits go: left, center (default) or right
+//   overflow=wrap  what to do with cells past the width: error (default),
+//                  truncate them or wrap them around to the other side
+//
+// Digits are base 36 in either case
+// Only one thing can fill the width, and not from inside a group
+use rand::distr::weighted::WeightedIndex;
+use rand::distr::Distribution;
+use rand::Rng;
+
+use crate::{from_char, Cell, Lattice, CELL0};
+
+// beyond this a truncated or wrapped config is surely a mistake
+const MAX_LEN: usize = 1 << 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Count {
+    Times(usize),
+    Fill,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Item {
+    Digits(Lattice),
+    Group(Vec<Item>, Count),
+    // chance of a cell not being 0, or None for all symbols alike
+    Random(Option<f64>, Count),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Align {
+    Left,
+    #[default]
+    Center,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Overflow {
+    #[default]
+    Error,
+    Truncate,
+    Wrap,
+}
+
+impl std::str::FromStr for Overflow {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(Overflow::Error),
+            "truncate" => Ok(Overflow::Truncate),
+            "wrap" => Ok(Overflow::Wrap),
+            _ => Err("overflow must be error, truncate or wrap"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigPattern {
+    body: Vec<Item>,
+    background: Lattice,
+    placed: Vec<(usize, Lattice)>,
+    align: Align,
+    overflow: Overflow,
+    // per symbol, for @ cells without their own density. Empty is uniform
+    weights: Vec<f64>,
+}
+
+impl ConfigPattern {
+    pub fn new(input: &str, radix: u32) -> Result<Self, &'static str> {
+        let mut pattern = ConfigPattern {
+            body: Vec::new(),
+            background: vec![CELL0],
+            placed: Vec::new(),
+            align: Align::default(),
+            overflow: Overflow::default(),
+            weights: Vec::new(),
+        };
+        let mut have_body = false;
+
+        if input.trim().is_empty() {
+            return Err("empty config");
+        }
+
+        for clause in input.split(';').map(str::trim).filter(|c| !c.is_empty()) {
+            if let Some(bg) = clause.strip_prefix("bg=") {
+                pattern.background = digits(bg, radix)?;
+                if pattern.background.is_empty() {
+                    return Err("empty background in config");
+                }
+            } else if let Some(align) = clause.strip_prefix("align=") {
+                pattern.align = match align {
+                    "left" => Align::Left,
+                    "center" => Align::Center,
+                    "right" => Align::Right,
+                    _ => return Err("align must be left, center or right"),
+                };
+            } else if let Some(overflow) = clause.strip_prefix("overflow=") {
+                pattern.overflow = overflow.parse()?;
+            } else if clause.contains(':') {
+                for place in clause.split(',') {
+                    let (at, cells) = place.split_once(':').ok_or("placements look like 5:1")?;
+                    let at = at.trim().parse().map_err(|_| "bad cell number in placement")?;
+                    pattern.placed.push((at, digits(cells.trim(), radix)?));
+                }
+            } else if have_body {
+                return Err("config has more than one run of digits");
+            } else {
+                let chars: Vec<char> = clause.chars().collect();
+                let mut parser = Parser { chars: &chars, pos: 0, radix, fills: 0 };
+                pattern.body = parser.items(0)?;
+                if parser.pos != chars.len() {
+                    return Err("unmatched ) in config");
+                }
+                have_body = true;
+            }
+        }
+
+        Ok(pattern)
+    }
+
+    // overrides any overflow= in the pattern
+    pub fn overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    // odds of each symbol in @ cells: one number is the chance of a cell not
+    // being 0 (split evenly over the other symbols), more is a weight per symbol
+    pub fn density(mut self, weights: &[f64], radix: u32) -> Result<Self, &'static str> {
+        self.weights = match weights {
+            [] => Vec::new(),
+            [p] if (0.0..=1.0).contains(p) => {
+                let mut w = vec![p / (radix - 1) as f64; radix as usize];
+                w[0] = 1.0 - p;
+                w
+            }
+            [_] => return Err("density must be between 0 and 1"),
+            w if w.len() != radix as usize => return Err("density needs one weight or one per symbol"),
+            w if w.iter().any(|x| !x.is_finite() || *x < 0.0) || w.iter().sum::<f64>() <= 0.0 => {
+                return Err("density weights must be positive")
+            }
+            w => w.to_vec(),
+        };
+        Ok(self)
+    }
+
+    // any random cells, which only come back the same with the same rng
+    pub fn is_random(&self) -> bool {
+        fn random(items: &[Item]) -> bool {
+            items.iter().any(|i| match i {
+                Item::Random(..) => true,
+                Item::Group(inner, _) => random(inner),
+                Item::Digits(_) => false,
+            })
+        }
+        random(&self.body)
+    }
+
+    // exactly width cells, or an error if the pattern doesn't fit and
+    // overflow is Error
+    pub fn build<R: Rng>(&self, width: usize, radix: u32, rng: &mut R) -> Result<Lattice, &'static str> {
+        if width == 0 {
+            return Err("lattice has no width");
+        }
+        let fixed = fixed_len(&self.body);
+        if fixed > width && self.overflow == Overflow::Error {
+            return Err("config is longer than the width");
+        }
+        if fixed > MAX_LEN {
+            return Err("config too long");
+        }
+
+        let mut random = Random {
+            radix,
+            weights: WeightedIndex::new(&self.weights).ok(),
+            rng,
+        };
+        let mut body = Lattice::with_capacity(width);
+        expand(&self.body, width.saturating_sub(fixed), &mut random, &mut body);
+
+        let mut config: Lattice = self.background.iter().cycle().take(width).copied().collect();
+
+        // negative when the body overflows, centering cuts from both ends
+        let spare = width as isize - body.len() as isize;
+        let left = match self.align {
+            Align::Left => 0,
+            Align::Center => spare.div_euclid(2),
+            Align::Right => spare,
+        };
+        self.place(&mut config, left, &body)?;
+
+        for (at, cells) in &self.placed {
+            let at = isize::try_from(*at).map_err(|_| "placement past the end of the lattice")?;
+            self.place(&mut config, at, cells)?;
+        }
+
+        Ok(config)
+    }
+
+    fn place(&self, config: &mut Lattice, at: isize, cells: &[Cell]) -> Result<(), &'static str> {
+        let width = config.len() as isize;
+        for (i, c) in cells.iter().enumerate() {
+            let x = at.saturating_add(i as isize);
+            match self.overflow {
+                _ if (0..width).contains(&x) => config[x as usize] = *c,
+                Overflow::Wrap => config[x.rem_euclid(width) as usize] = *c,
+                Overflow::Truncate => {}
+                Overflow::Error => return Err("placement past the end of the lattice"),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn digits(s: &str, radix: u32) -> Result<Lattice, &'static str> {
+    if !s.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err("invalid character in config");
+    }
+    let cells: Lattice = s.chars().map(from_char).collect();
+    if cells.iter().any(|c| *c as u32 >= radix) {
+        return Err("config has digits outside the radix");
+    }
+    Ok(cells)
+}
+
+// cells needed by everything but the fill
+fn fixed_len(items: &[Item]) -> usize {
+    items
+        .iter()
+        .map(|i| match i {
+            Item::Digits(d) => d.len(),
+            Item::Group(inner, Count::Times(n)) => n.saturating_mul(fixed_len(inner)),
+            Item::Random(_, Count::Times(n)) => *n,
+            _ => 0,
+        })
+        .fold(0, usize::saturating_add)
+}
+
+fn expand<R: Rng>(items: &[Item], fill: usize, random: &mut Random<R>, out: &mut Lattice) {
+    for item in items {
+        match item {
+            Item::Digits(d) => out.extend_from_slice(d),
+            // nothing to repeat, however many times, eg (()*9)*9
+            Item::Group(inner, Count::Times(_)) if fixed_len(inner) == 0 => {}
+            Item::Group(inner, Count::Times(n)) => {
+                for _ in 0..*n {
+                    expand(inner, 0, random, out);
+                }
+            }
+            Item::Group(inner, Count::Fill) => {
+                let mut once = Lattice::new();
+                expand(inner, 0, random, &mut once);
+                out.extend(once.iter().cycle().take(if once.is_empty() { 0 } else { fill }));
+            }
+            Item::Random(density, count) => {
+                let n = match count {
+                    Count::Times(n) => *n,
+                    Count::Fill => fill,
+                };
+                for _ in 0..n {
+                    out.push(random.cell(*density));
+                }
+            }
+        }
+    }
+}
+
+struct Random<'a, R> {
+    radix: u32,
+    weights: Option<WeightedIndex<f64>>,
+    rng: &'a mut R,
+}
+
+impl<R: Rng> Random<'_, R> {
+    fn cell(&mut self, density: Option<f64>) -> Cell {
+        match (density, &self.weights) {
+            (None, None) => self.rng.random_range(CELL0..self.radix as Cell),
+            (None, Some(w)) => w.sample(self.rng) as Cell,
+            (Some(p), _) if self.rng.random_bool(p) => self.rng.random_range(1..self.radix as Cell),
+            (Some(_), _) => CELL0,
+        }
+    }
+}
+
+struct Parser<'a> {
+    chars: &'a [char],
+    pos: usize,
+    radix: u32,
+    fills: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    // up to the end or a ) closing this group
+    fn items(&mut self, depth: usize) -> Result<Vec<Item>, &'static str> {
+        let mut items = Vec::new();
+
+        while let Some(c) = self.peek() {
+            match c {
+                '(' => {
+                    self.pos += 1;
+                    let inner = self.items(depth + 1)?;
+                    if self.peek() != Some(')') {
+                        return Err("unmatched ( in config");
+                    }
+                    self.pos += 1;
+                    let count = if self.peek() == Some('*') {
+                        self.pos += 1;
+                        self.count(depth)?
+                    } else {
+                        Count::Times(1)
+                    };
+                    items.push(Item::Group(inner, count));
+                }
+                ')' => break,
+                '@' => {
+                    self.pos += 1;
+                    let density = self.density()?;
+                    let count = if self.peek() == Some('*') {
+                        self.pos += 1;
+                        self.count(depth)?
+                    } else {
+                        self.fill(depth)?
+                    };
+                    items.push(Item::Random(density, count));
+                }
+                c if c.is_ascii_alphanumeric() => {
+                    let start = self.pos;
+                    while self.peek().is_some_and(|c| c.is_ascii_alphanumeric()) {
+                        self.pos += 1;
+                    }
+                    let run: String = self.chars[start..self.pos].iter().collect();
+                    items.push(Item::Digits(digits(&run, self.radix)?));
+                }
+                _ => return Err("invalid character in config"),
+            }
+        }
+
+        Ok(items)
+    }
+
+    // a number, or nothing for a fill
+    fn count(&mut self, depth: usize) -> Result<Count, &'static str> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return self.fill(depth);
+        }
+        let n: String = self.chars[start..self.pos].iter().collect();
+        n.parse().map(Count::Times).map_err(|_| "repeat count too large")
+    }
+
+    fn fill(&mut self, depth: usize) -> Result<Count, &'static str> {
+        self.fills += 1;
+        if self.fills > 1 {
+            return Err("only one thing in a config can fill the width");
+        }
+        if depth > 0 {
+            return Err("can't fill the width from inside a group");
+        }
+        Ok(Count::Fill)
+    }
+
+    // @0.3, only taken as a density with the decimal point so @01 is random
+    // cells then 01
+    fn density(&mut self) -> Result<Option<f64>, &'static str> {
+        let end = self.chars[self.pos..]
+            .iter()
+            .position(|c| !(c.is_ascii_digit() || *c == '.'))
+            .map_or(self.chars.len(), |n| self.pos + n);
+        let num: String = self.chars[self.pos..end].iter().collect();
+        if !num.contains('.') {
+            return Ok(None);
+        }
+        self.pos = end;
+        match num.parse::<f64>() {
+            Ok(p) if (0.0..=1.0).contains(&p) => Ok(Some(p)),
+            _ => Err("random density must be between 0 and 1"),
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use std::io;
+
+use crate::{Cell, Rgb, WriterOpts};
+
+// Turns rows of cells into rows of pixels for the raster image writers.
+// Pixels are palette indexes: a cell is its own color, grid lines get the
+// color after the last cell. Each cell becomes a scale x scale block, with
+// grid on its last row and column
+pub(crate) struct Raster {
+    pub scale: usize,
+    grid: Option<Cell>,
+    colors: Vec<Rgb>,
+}
+
+impl Raster {
+    pub fn new(radix: u32, opts: &WriterOpts) -> io::Result<Self> {
+        if opts.grid && opts.scale < 2 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "grid lines need a scale of at least 2"));
+        }
+
+        Ok(Self {
+            scale: opts.scale,
+            grid: if opts.grid { Some(radix as Cell) } else { None },
+            colors: opts.palette.io_colors(radix)?,
+        })
+    }
+
+    // rgb for every pixel value row() can produce
+    pub fn palette(&self) -> Vec<Rgb> {
+        let mut colors = self.colors.clone();
+
+        if self.grid.is_some() {
+            colors.push((0x80, 0x80, 0x80));
+        }
+
+        colors
+    }
+
+    // pixel row i in [0,scale) of the row of cells v, out is v.len() * scale long
+    pub fn row(&self, v: &[Cell], i: usize, out: &mut [Cell]) {
+        let scale = self.scale;
+
+        match self.grid {
+            Some(g) if i == scale - 1 => out.fill(g),
+            grid => {
+                for (x, c) in v.iter().enumerate() {
+                    let px = &mut out[x * scale..(x + 1) * scale];
+                    px.fill(*c);
+                    if let Some(g) = grid {
+                        px[scale - 1] = g;
+                    }
+                }
+            }
+        }
+    }
+}
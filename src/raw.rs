@@ -0,0 +1,270 @@
+// Raw output: a short header saying what made the run, then the cells.
+// Integers are little endian.
+//
+//   magic     "CA1D"
+//   version   u8, 1
+//   flags     u8, bit 0 set if rows are bit packed
+//   radix     u8
+//   border    u8, 0 ring 1 fixed
+//   nabor     u32
+//   kind      u8, 0 rule 1 code
+//   rule      u32 byte count, then the rule number's bytes
+//   width     u64
+//   gens      u64, 0 if not known up front (eg --follow), read to the end
+//   cells     a row per generation: width bytes, or packed into 1, 2 or 4
+//             bits per cell (the least that fits radix), first cell in the
+//             high bits and each row starting on a fresh byte
+use std::io::{self, Read, Write};
+
+use num_bigint::BigUint;
+
+use crate::{cell_bits, pack_cells, Border, CAEvalType, Lattice, RunInfo, WriterOpts, CA, CAWriter};
+
+pub(crate) const MAGIC: &[u8; 4] = b"CA1D";
+pub(crate) const VERSION: u8 = 1;
+const PACKED: u8 = 1;
+
+// what a Raw file says about itself
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawHeader {
+    pub radix: u32,
+    pub run: RunInfo,
+    pub width: usize,
+    // 0 when the writer didn't know
+    pub generations: usize,
+    pub packed: bool,
+}
+
+impl RawHeader {
+    // the automaton that made the file, eg to carry on from its last row
+    pub fn to_ca(&self) -> CA {
+        self.run.to_ca(self.radix)
+    }
+
+    fn row_len(&self) -> io::Result<usize> {
+        let bits = self.width.checked_mul(self.bits()).ok_or_else(|| invalid("raw file too wide"))?;
+        Ok(bits.div_ceil(8))
+    }
+
+    fn bits(&self) -> usize {
+        if self.packed {
+            cell_bits(self.radix)
+        } else {
+            8
+        }
+    }
+
+    fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let (kind, rule) = match &self.run.rule {
+            CAEvalType::Rule(n) => (0u8, n),
+            CAEvalType::Code(n) => (1u8, n),
+        };
+        let rule = rule.to_bytes_le();
+        let border = match self.run.border {
+            Border::Ring => 0u8,
+            Border::Fixed => 1u8,
+        };
+
+        w.write_all(MAGIC)?;
+        w.write_all(&[VERSION, if self.packed { PACKED } else { 0 }, self.radix as u8, border])?;
+        w.write_all(&self.run.nabor_size.to_le_bytes())?;
+        w.write_all(&[kind])?;
+        w.write_all(&(rule.len() as u32).to_le_bytes())?;
+        w.write_all(&rule)?;
+        w.write_all(&(self.width as u64).to_le_bytes())?;
+        w.write_all(&(self.generations as u64).to_le_bytes())
+    }
+
+    fn read<R: Read>(r: &mut R) -> io::Result<Self> {
+        let mut magic = [0u8; 4];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not a ca1d raw file"));
+        }
+
+        let [version, flags, radix, border] = read_array(r)?;
+        if version != VERSION {
+            return Err(invalid("unsupported raw file version"));
+        }
+        if !(2..=36).contains(&radix) {
+            return Err(invalid("raw file radix out of range"));
+        }
+        let border = match border {
+            0 => Border::Ring,
+            1 => Border::Fixed,
+            _ => return Err(invalid("unknown border in raw file")),
+        };
+
+        let nabor_size = u32::from_le_bytes(read_array(r)?);
+        let [kind] = read_array(r)?;
+        let len = u32::from_le_bytes(read_array(r)?) as usize;
+        let mut rule = Vec::new();
+        r.take(len as u64).read_to_end(&mut rule)?;
+        if rule.len() != len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let rule = BigUint::from_bytes_le(&rule);
+        let rule = match kind {
+            0 => CAEvalType::Rule(rule),
+            1 => CAEvalType::Code(rule),
+            _ => return Err(invalid("unknown rule kind in raw file")),
+        };
+
+        let width = u64::from_le_bytes(read_array(r)?);
+        let generations = u64::from_le_bytes(read_array(r)?);
+
+        Ok(RawHeader {
+            radix: radix as u32,
+            run: RunInfo {
+                nabor_size,
+                rule,
+                border,
+                ..RunInfo::default()
+            },
+            width: usize::try_from(width).map_err(|_| invalid("raw file too wide"))?,
+            generations: usize::try_from(generations).map_err(|_| invalid("raw file too long"))?,
+            packed: flags & PACKED != 0,
+        })
+    }
+}
+
+fn read_array<R: Read, const N: usize>(r: &mut R) -> io::Result<[u8; N]> {
+    let mut buf = [0u8; N];
+    r.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn invalid(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+pub struct RawWriter<W: Write> {
+    w: W,
+    header: RawHeader,
+    row: Vec<u8>,
+    written: usize,
+}
+impl<W: Write> RawWriter<W> {
+    // hite is written as the generation count, 0 if it isn't known.
+    // opts.pack packs the cells, opts.run goes in the header
+    pub fn new(mut w: W, radix: u32, width: usize, hite: usize, opts: &WriterOpts) -> io::Result<Self> {
+        let header = RawHeader {
+            radix,
+            run: opts.run.clone(),
+            width,
+            generations: hite,
+            packed: opts.pack,
+        };
+        header.write(&mut w)?;
+
+        Ok(Self {
+            w,
+            row: vec![0; header.row_len()?],
+            header,
+            written: 0,
+        })
+    }
+}
+impl<W: Write> CAWriter for RawWriter<W> {
+    fn write_line(&mut self, v: &Lattice) -> io::Result<()> {
+        self.written += 1;
+        if !self.header.packed {
+            return self.w.write_all(v);
+        }
+        pack_cells(v, self.header.bits(), &mut self.row);
+        self.w.write_all(&self.row)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        let expected = self.header.generations;
+        if expected != 0 && expected != self.written {
+            return Err(io::Error::other(format!(
+                "raw header says {} generations but {} were written",
+                expected, self.written
+            )));
+        }
+        self.w.flush()
+    }
+}
+
+// reads a Raw file back a generation at a time
+pub struct RawReader<R: Read> {
+    r: R,
+    header: RawHeader,
+    // grows as a row comes in, so a header can't ask for more than the file has
+    row: Vec<u8>,
+    row_len: usize,
+    read: usize,
+    // stops the iterator after an error
+    failed: bool,
+}
+impl<R: Read> RawReader<R> {
+    pub fn new(mut r: R) -> io::Result<Self> {
+        let header = RawHeader::read(&mut r)?;
+
+        Ok(Self {
+            r,
+            row: Vec::new(),
+            row_len: header.row_len()?,
+            header,
+            read: 0,
+            failed: false,
+        })
+    }
+
+    pub fn header(&self) -> &RawHeader {
+        &self.header
+    }
+
+    // false at a clean end of file, which is only fine if we weren't told
+    // how many rows to expect
+    fn fill(&mut self) -> io::Result<bool> {
+        self.row.clear();
+        let got = (&mut self.r).take(self.row_len as u64).read_to_end(&mut self.row)?;
+
+        if got == 0 && self.header.generations == 0 {
+            return Ok(false);
+        }
+        if got < self.row_len {
+            return Err(invalid("raw file is cut short"));
+        }
+        Ok(true)
+    }
+
+    fn next_row(&mut self) -> io::Result<Option<Lattice>> {
+        if self.header.generations != 0 && self.read == self.header.generations {
+            return Ok(None);
+        }
+        if !self.fill()? {
+            return Ok(None);
+        }
+        self.read += 1;
+
+        let bits = self.header.bits();
+        let per_byte = 8 / bits;
+        let mask = ((1u16 << bits) - 1) as u8;
+        let row: Lattice = (0..self.header.width)
+            .map(|i| {
+                let shift = 8 - bits * (i % per_byte + 1);
+                (self.row[i / per_byte] >> shift) & mask
+            })
+            .collect();
+
+        if row.iter().any(|c| *c as u32 >= self.header.radix) {
+            return Err(invalid("cell in raw file out of radix"));
+        }
+        Ok(Some(row))
+    }
+}
+impl<R: Read> Iterator for RawReader<R> {
+    type Item = io::Result<Lattice>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let row = self.next_row();
+        self.failed = row.is_err();
+        row.transpose()
+    }
+}
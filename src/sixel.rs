@@ -0,0 +1,124 @@
+use std::io;
+use std::io::Write;
+
+use crate::{CAWriter, Cell, Lattice, Raster, WriterOpts};
+
+// sixel pixels are in bands of six rows
+const BAND: usize = 6;
+
+// Sixel graphics for terminals that support it (mlterm, foot, WezTerm, xterm -ti vt340...)
+// The palette goes into the color registers up front, then each band of six
+// pixel rows is emitted as soon as it fills up so the image streams in
+pub struct SixelWriter<W: Write> {
+    w: W,
+    colors: usize,
+    raster: Raster,
+    // width in pixels
+    width: usize,
+    band: Vec<Cell>,
+    rows: usize,
+    sbuf: Vec<u8>,
+}
+
+impl<W: Write> SixelWriter<W> {
+    pub fn new(mut w: W, radix: u32, width: usize, hite: usize, opts: &WriterOpts) -> io::Result<Self> {
+        let raster = Raster::new(radix, opts)?;
+        let width = width * raster.scale;
+
+        // DCS, then raster attributes: 1:1 aspect and size in pixels
+        write!(w, "\x1bPq\"1;1;{};{}", width, hite * raster.scale)?;
+
+        let colors = raster.palette();
+        for (c, (r, g, b)) in colors.iter().enumerate() {
+            let pct = |x: u8| x as u32 * 100 / 255;
+            write!(w, "#{};2;{};{};{}", c, pct(*r), pct(*g), pct(*b))?;
+        }
+
+        Ok(Self {
+            w,
+            colors: colors.len(),
+            raster,
+            width,
+            band: vec![0; width * BAND],
+            rows: 0,
+            sbuf: Vec::with_capacity(width),
+        })
+    }
+
+    // one sixel character repeated n times, run length encoded when it pays
+    fn push_run(&mut self, ch: u8, n: usize) {
+        if n > 3 {
+            self.sbuf.extend_from_slice(format!("!{}", n).as_bytes());
+            self.sbuf.push(ch);
+        } else {
+            for _ in 0..n {
+                self.sbuf.push(ch);
+            }
+        }
+    }
+
+    fn write_band(&mut self) -> io::Result<()> {
+        for c in 0..self.colors as Cell {
+            let band = &self.band[..self.rows * self.width];
+            if !band.contains(&c) {
+                continue;
+            }
+
+            self.sbuf.clear();
+            write!(self.sbuf, "#{}", c)?;
+
+            let mut run = (0, 0);
+            for x in 0..self.width {
+                let mut bits = 0;
+                for y in 0..self.rows {
+                    if self.band[y * self.width + x] == c {
+                        bits |= 1 << y;
+                    }
+                }
+
+                let ch = 63 + bits;
+                if ch != run.0 {
+                    self.push_run(run.0, run.1);
+                    run = (ch, 0);
+                }
+                run.1 += 1;
+            }
+            self.push_run(run.0, run.1);
+
+            // graphics carriage return, next color overprints the same band
+            self.sbuf.push(b'$');
+            self.w.write_all(&self.sbuf)?;
+        }
+
+        // graphics new line
+        self.rows = 0;
+        self.w.write_all(b"-")?;
+        self.w.flush()
+    }
+}
+
+impl<W: Write> CAWriter for SixelWriter<W> {
+    fn write_line(&mut self, v: &Lattice) -> io::Result<()> {
+        for i in 0..self.raster.scale {
+            let row = &mut self.band[self.rows * self.width..(self.rows + 1) * self.width];
+            self.raster.row(v, i, row);
+
+            self.rows += 1;
+            if self.rows == BAND {
+                self.write_band()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if self.rows > 0 {
+            self.write_band()?;
+        }
+
+        // string terminator
+        self.w.write_all(b"\x1b\\")?;
+        self.w.flush()
+    }
+}
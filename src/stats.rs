@@ -0,0 +1,55 @@
+// per generation numbers for the structured outputs
+use crate::Lattice;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenStats {
+    // fraction of the cells holding each symbol
+    pub density: Vec<f64>,
+    // stretches of equal neighboring cells, not wrapping around
+    pub runs: usize,
+    // cells that changed since the last generation, None for the first
+    pub hamming: Option<usize>,
+}
+
+pub struct Stats {
+    counts: Vec<usize>,
+    prev: Option<Lattice>,
+}
+
+impl Stats {
+    pub fn new(radix: u32) -> Self {
+        Self {
+            counts: vec![0; radix as usize],
+            prev: None,
+        }
+    }
+
+    pub fn update(&mut self, v: &Lattice) -> GenStats {
+        self.counts.fill(0);
+        for c in v {
+            self.counts[*c as usize] += 1;
+        }
+        let density = self.counts.iter().map(|n| *n as f64 / v.len() as f64).collect();
+
+        let runs = if v.is_empty() {
+            0
+        } else {
+            1 + v.windows(2).filter(|p| p[0] != p[1]).count()
+        };
+
+        let hamming = self
+            .prev
+            .as_ref()
+            .map(|p| p.iter().zip(v).filter(|(a, b)| a != b).count());
+
+        match &mut self.prev {
+            Some(p) => {
+                p.clear();
+                p.extend_from_slice(v);
+            }
+            None => self.prev = Some(v.clone()),
+        }
+
+        GenStats { density, runs, hamming }
+    }
+}
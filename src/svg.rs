@@ -0,0 +1,91 @@
+use std::io;
+use std::io::Write;
+
+use crate::{CAWriter, Cell, Lattice, WriterOpts, CELL0};
+
+// Vector output, streamed a row at a time.
+// The image is in cell units (viewBox) and scaled up by opts.scale, each row is
+// drawn as one rect per run of equal cells over a background of CELL0 color
+pub struct SVGWriter<W: Write> {
+    w: W,
+    // fill for each symbol
+    colors: Vec<String>,
+    width: usize,
+    hite: usize,
+    grid: bool,
+    row: usize,
+}
+
+impl<W: Write> SVGWriter<W> {
+    pub fn new(mut w: W, radix: u32, width: usize, hite: usize, opts: &WriterOpts) -> io::Result<Self> {
+        let colors = opts.palette.css_colors(radix)?;
+
+        writeln!(
+            w,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}" shape-rendering="crispEdges">"#,
+            width * opts.scale,
+            hite * opts.scale,
+            width,
+            hite
+        )?;
+        writeln!(w, r#"<rect width="{}" height="{}" fill="{}"/>"#, width, hite, colors[CELL0 as usize])?;
+
+        Ok(Self {
+            w,
+            colors,
+            width,
+            hite,
+            grid: opts.grid,
+            row: 0,
+        })
+    }
+
+    fn write_run(&mut self, x: usize, len: usize, c: Cell) -> io::Result<()> {
+        if c == CELL0 {
+            return Ok(());
+        }
+
+        writeln!(
+            self.w,
+            r#"<rect x="{}" y="{}" width="{}" height="1" fill="{}"/>"#,
+            x,
+            self.row,
+            len,
+            self.colors[c as usize]
+        )
+    }
+}
+
+impl<W: Write> CAWriter for SVGWriter<W> {
+    fn write_line(&mut self, v: &Lattice) -> io::Result<()> {
+        let mut start = 0;
+
+        for x in 1..=v.len() {
+            if x == v.len() || v[x] != v[start] {
+                self.write_run(start, x - start, v[start])?;
+                start = x;
+            }
+        }
+
+        self.row += 1;
+        Ok(())
+    }
+
+    // grid goes on top of everything as one pattern filled rect
+    fn finish(&mut self) -> io::Result<()> {
+        if self.grid {
+            writeln!(
+                self.w,
+                concat!(
+                    r#"<defs><pattern id="grid" width="1" height="1" patternUnits="userSpaceOnUse">"#,
+                    r##"<path d="M1 0H0V1" fill="none" stroke="#808080" stroke-width="0.1"/>"##,
+                    r#"</pattern></defs>"#
+                )
+            )?;
+            writeln!(self.w, r#"<rect width="{}" height="{}" fill="url(#grid)"/>"#, self.width, self.hite)?;
+        }
+
+        writeln!(self.w, "</svg>")?;
+        self.w.flush()
+    }
+}
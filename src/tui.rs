@@ -0,0 +1,394 @@
+// Interactive explorer: draws the run full screen with one of the terminal
+// writers and redraws it as keys change the rule, config, border or output
+use std::cell::RefCell;
+use std::io::{self, BufWriter, Write};
+use std::rc::Rc;
+use std::time::Duration;
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::terminal::{self, ClearType};
+use crossterm::{cursor, execute, queue};
+
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+
+use rand::Rng;
+
+use ca1d::{automate_with, get_writer, Border, CAEvalType, CAWriter, ColorDepth, Lattice, Output, OutputSpec, WriterOpts, CA};
+
+use crate::Opts;
+
+// what o cycles through, skipping those without enough symbols for the radix
+const STYLES: [Output; 7] = [
+    Output::UnicodeAnsi,
+    Output::AnsiGrey,
+    Output::Quadrant,
+    Output::Braille,
+    Output::Unicode,
+    Output::Ascii,
+    Output::Cell,
+];
+
+const HELP: &str = "q:quit space:run/pause arrows/pgup/pgdn/home:scroll +/-/r:rule s:reseed e:edit b:border o:output";
+
+// time between lines when running
+const TICK: Duration = Duration::from_millis(100);
+
+// the writers own their destination, this lets us read the frame back
+#[derive(Clone, Default)]
+struct Frame(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Frame {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// the left of a lattice wider than the screen
+struct Clip<'a>(&'a mut dyn CAWriter, usize);
+
+impl CAWriter for Clip<'_> {
+    fn write_line(&mut self, v: &Lattice) -> io::Result<()> {
+        self.0.write_line(&v[..self.1.min(v.len())].to_vec())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.0.finish()
+    }
+}
+
+// puts the terminal back however run() returns
+struct Screen;
+
+impl Screen {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(Screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+struct Explorer {
+    // kept in step with what is on screen, so cmd_line() can reproduce it.
+    // from is the generation on the top line
+    opts: Opts,
+    ca: CA,
+    space: Option<BigUint>,
+    depth: ColorDepth,
+    start: Lattice,
+    // cached generation top_gen, saves stepping from the start on every line
+    top: Lattice,
+    top_gen: usize,
+    running: bool,
+    edit: Option<String>,
+    note: &'static str,
+}
+
+impl Explorer {
+    fn new(mut opts: Opts) -> Self {
+        let output = opts
+            .term_output()
+            .filter(|o| STYLES.contains(o) && o.supports(opts.radix))
+            .unwrap_or(Output::UnicodeAnsi);
+        opts.output = vec![OutputSpec { output, path: None }];
+
+        Self {
+            ca: opts.to_ca(),
+            space: opts.rule_number.rule_space(opts.radix, opts.nabor_size),
+            depth: opts.color.depth(true),
+            start: Lattice::new(),
+            top: Lattice::new(),
+            top_gen: 0,
+            running: false,
+            edit: None,
+            note: "",
+            opts,
+        }
+    }
+
+    fn output(&self) -> Output {
+        self.opts.output[0].output
+    }
+
+    // generations per text line
+    fn line(&self) -> usize {
+        self.output().packing().1
+    }
+
+    // lattice width and generations that fit, less the status line
+    fn size(&self) -> io::Result<(usize, usize)> {
+        let (cols, rows) = terminal::size()?;
+        let (pw, ph) = self.output().packing();
+        Ok((pw * cols as usize, ph * (rows as usize).saturating_sub(1).max(1)))
+    }
+
+    fn restart(&mut self, width: usize) {
+        let mut opts = self.opts.clone();
+        opts.width = width;
+        self.start = opts.config().unwrap_or_else(|e| {
+            self.note = e;
+            vec![0; width]
+        });
+        self.rewind();
+    }
+
+    // the run changed, forget the cached generation
+    fn rewind(&mut self) {
+        self.top = self.start.clone();
+        self.top_gen = 0;
+    }
+
+    fn seek(&mut self) {
+        if self.opts.from < self.top_gen {
+            self.rewind();
+        }
+        while self.top_gen < self.opts.from {
+            self.top = self.ca.gtf(&self.top);
+            self.top_gen += 1;
+        }
+    }
+
+    fn set_rule(&mut self, n: BigUint) {
+        self.opts.rule_number = match self.opts.rule_number {
+            CAEvalType::Rule(_) => CAEvalType::Rule(n),
+            CAEvalType::Code(_) => CAEvalType::Code(n),
+        };
+        self.ca = self.opts.to_ca();
+        self.rewind();
+    }
+
+    fn rule(&self) -> BigUint {
+        match &self.opts.rule_number {
+            CAEvalType::Rule(n) | CAEvalType::Code(n) => n.clone(),
+        }
+    }
+
+    fn next_rule(&mut self) {
+        let n = self.rule() + 1u32;
+        match &self.space {
+            Some(space) if &n >= space => self.set_rule(BigUint::zero()),
+            _ => self.set_rule(n),
+        }
+    }
+
+    fn prev_rule(&mut self) {
+        let n = self.rule();
+        if !n.is_zero() {
+            self.set_rule(n - 1u32);
+        } else if let Some(space) = &self.space {
+            self.set_rule(space - BigUint::one());
+        }
+    }
+
+    fn random_rule(&mut self) {
+        let Some(space) = &self.space else {
+            self.note = "rule space too large to pick from";
+            return;
+        };
+        // extra bytes keep the bias from the modulo negligible
+        let mut bytes = vec![0u8; (space.bits() as usize).div_ceil(8) + 8];
+        rand::rng().fill(&mut bytes[..]);
+        let n = BigUint::from_bytes_le(&bytes) % space;
+        self.set_rule(n);
+    }
+
+    fn next_output(&mut self) {
+        let i = STYLES.iter().position(|o| *o == self.output()).unwrap_or(0);
+        let next = STYLES
+            .iter()
+            .cycle()
+            .skip(i + 1)
+            .find(|o| o.supports(self.opts.radix))
+            .unwrap();
+        self.opts.output[0].output = *next;
+        // the width in cells depends on the packing
+        self.start.clear();
+    }
+
+    fn use_config(&mut self, config: String) {
+        let old = std::mem::replace(&mut self.opts.start_config, config);
+        if let Err(e) = self.opts.pattern() {
+            self.note = e;
+            self.opts.start_config = old;
+            return;
+        }
+        self.opts.pick_seed();
+        self.start.clear();
+    }
+
+    fn is_random(&self) -> bool {
+        self.opts.pattern().is_ok_and(|p| p.is_random())
+    }
+
+    // false when it's time to quit
+    fn key(&mut self, k: KeyEvent) -> bool {
+        self.note = "";
+
+        if let Some(buf) = &mut self.edit {
+            match k.code {
+                KeyCode::Enter => {
+                    let config = self.edit.take().unwrap();
+                    self.use_config(config);
+                }
+                KeyCode::Esc => self.edit = None,
+                KeyCode::Backspace => {
+                    buf.pop();
+                }
+                KeyCode::Char(c) => buf.push(c),
+                _ => {}
+            }
+            return true;
+        }
+
+        let page = self.size().map_or(1, |(_, h)| h);
+        let from = self.opts.from;
+
+        match k.code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Char('c') if k.modifiers.contains(KeyModifiers::CONTROL) => return false,
+            KeyCode::Char(' ') => self.running = !self.running,
+            KeyCode::Up | KeyCode::Char('k') => self.opts.from = from.saturating_sub(self.line()),
+            KeyCode::Down | KeyCode::Char('j') => self.opts.from = from + self.line(),
+            KeyCode::PageUp => self.opts.from = from.saturating_sub(page),
+            KeyCode::PageDown => self.opts.from = from + page,
+            KeyCode::Home => self.opts.from = 0,
+            KeyCode::Char('+') | KeyCode::Char('=') => self.next_rule(),
+            KeyCode::Char('-') => self.prev_rule(),
+            KeyCode::Char('r') => self.random_rule(),
+            KeyCode::Char('s') => {
+                if !self.is_random() {
+                    self.opts.start_config = "@".to_string();
+                }
+                self.opts.seed = Some(rand::rng().random());
+                self.start.clear();
+            }
+            KeyCode::Char('e') => {
+                let current = if self.opts.start_config == "@" {
+                    String::new()
+                } else {
+                    self.opts.start_config.clone()
+                };
+                self.edit = Some(current);
+            }
+            KeyCode::Char('b') => {
+                self.opts.border = match self.opts.border {
+                    Border::Ring => Border::Fixed,
+                    Border::Fixed => Border::Ring,
+                };
+                self.ca = self.opts.to_ca();
+                self.rewind();
+            }
+            KeyCode::Char('o') => self.next_output(),
+            _ => {}
+        }
+
+        true
+    }
+
+    fn status(&self) -> String {
+        if let Some(buf) = &self.edit {
+            return format!("config: {}_  (enter to use, esc to cancel)", buf);
+        }
+
+        format!(
+            "{} {} {} t={} {}  {}",
+            self.opts.rule_number,
+            self.opts.border,
+            self.output(),
+            self.opts.from,
+            if self.running { "running" } else { "paused" },
+            if self.note.is_empty() { HELP } else { self.note },
+        )
+    }
+
+    fn draw(&mut self, out: &mut impl Write) -> io::Result<()> {
+        // an explicit --width is kept, otherwise the lattice fits the screen
+        let (screen, hite) = self.size()?;
+        let width = if self.opts.width > 0 { self.opts.width } else { screen };
+        if width != self.start.len() {
+            self.restart(width);
+        }
+        self.seek();
+
+        let frame = Frame::default();
+        let wopts = WriterOpts {
+            color: self.depth,
+            ..self.opts.writer_opts()
+        };
+        let shown = width.min(screen);
+        let mut writer = get_writer(self.output(), frame.clone(), self.opts.radix, shown, hite, &wopts)?;
+        automate_with(&mut Clip(writer.as_mut(), shown), 0, hite, &self.ca, &self.top)?;
+        drop(writer);
+
+        // raw mode doesn't turn \n into \r\n for us
+        let lines = frame.0.borrow();
+        let (cols, rows) = terminal::size()?;
+
+        queue!(out, terminal::BeginSynchronizedUpdate, cursor::MoveTo(0, 0), terminal::Clear(ClearType::All))?;
+        for line in lines.split_inclusive(|b| *b == b'\n') {
+            out.write_all(line.strip_suffix(b"\n").unwrap_or(line))?;
+            out.write_all(b"\r\n")?;
+        }
+
+        let status: String = self.status().chars().take(cols as usize).collect();
+        queue!(out, cursor::MoveTo(0, rows.saturating_sub(1)), terminal::Clear(ClearType::CurrentLine))?;
+        write!(out, "{}", status)?;
+        queue!(out, terminal::EndSynchronizedUpdate)?;
+        out.flush()
+    }
+
+    // options for running what was last shown from the command line. A width
+    // from the terminal is left out unless a random start needs it to come
+    // back the same
+    fn into_opts(mut self) -> Opts {
+        if self.opts.width == 0 && self.is_random() {
+            self.opts.width = self.start.len();
+        }
+        self.opts
+    }
+}
+
+// runs until q, returns the options for what was on screen
+pub fn run(opts: &Opts) -> io::Result<Opts> {
+    let mut explorer = Explorer::new(opts.clone());
+    let screen = Screen::enter()?;
+    let mut out = BufWriter::new(io::stdout());
+    let mut dirty = true;
+
+    loop {
+        if dirty {
+            explorer.draw(&mut out)?;
+            dirty = false;
+        }
+
+        if event::poll(TICK)? {
+            match event::read()? {
+                Event::Key(k) if k.kind == KeyEventKind::Press => {
+                    if !explorer.key(k) {
+                        break;
+                    }
+                    dirty = true;
+                }
+                Event::Resize(..) => dirty = true,
+                _ => {}
+            }
+        } else if explorer.running {
+            explorer.opts.from += explorer.line();
+            dirty = true;
+        }
+    }
+
+    drop(screen);
+    Ok(explorer.into_opts())
+}
@@ -13,7 +13,7 @@ fn code600() {
 		Border::Ring,
 	);
 
-	let (_, config) = automate(Output::Cell, 0, 3, &ca, &start_config);
+	let (_, config) = automate(Output::Cell, 0, 3, &ca, &start_config).unwrap();
 
 	assert!(config == vec![2,2,0,2,2]);
 }
@@ -28,7 +28,7 @@ fn code777() {
 		Border::Ring,
 	);
 
-	let (_, config) = automate(Output::Cell, 0, 3, &ca, &start_config);
+	let (_, config) = automate(Output::Cell, 0, 3, &ca, &start_config).unwrap();
 
 	assert!(config == vec![1,1,0,0,0,1,1]);
 }
@@ -0,0 +1,64 @@
+use ca1d::{CAWriter, GlyphWriter, Lattice};
+
+fn write(mut w: impl CAWriter, lines: &[Lattice]) {
+	for l in lines {
+		w.write_line(l).unwrap();
+	}
+	w.finish().unwrap();
+}
+
+fn half(radix: u32, lines: &[Lattice]) -> String {
+	let mut out: Vec<u8> = Vec::new();
+	write(GlyphWriter::half(&mut out, radix, lines[0].len()), lines);
+	String::from_utf8(out).unwrap()
+}
+
+fn quadrant(radix: u32, lines: &[Lattice]) -> String {
+	let mut out: Vec<u8> = Vec::new();
+	write(GlyphWriter::quadrant(&mut out, radix, lines[0].len()), lines);
+	String::from_utf8(out).unwrap()
+}
+
+fn braille(radix: u32, lines: &[Lattice]) -> String {
+	let mut out: Vec<u8> = Vec::new();
+	write(GlyphWriter::braille(&mut out, radix, lines[0].len()), lines);
+	String::from_utf8(out).unwrap()
+}
+
+fn dots(s: &str) -> u32 {
+	s.chars().filter(|c| *c != '\n').map(|c| (c as u32 - 0x2800).count_ones()).sum()
+}
+
+#[test]
+fn half_rows() {
+	assert!(half(2, &[vec![1,0,1], vec![0,1,1]]) == "▀▄█\n");
+	// three generations make a line and a half, the rest is off
+	assert!(half(2, &[vec![1,0], vec![0,0], vec![1,1]]) == "▀ \n▀▀\n");
+}
+
+#[test]
+fn quadrant_rows() {
+	// the odd cell at the right edge gets a half empty glyph
+	assert!(quadrant(2, &[vec![1,0,1], vec![0,1,1]]) == "▚▌\n");
+	assert!(quadrant(2, &[vec![1,1], vec![1,1], vec![0,1]]) == "█\n▝\n");
+}
+
+#[test]
+fn braille_rows() {
+	assert!(braille(2, &vec![vec![1,1]; 4]) == "⣿\n");
+	assert!(braille(2, &[vec![1,0], vec![0,0], vec![0,0], vec![0,1]]) == "⢁\n");
+	assert!(braille(2, &[vec![1,1]]) == "⠉\n");
+	assert!(braille(2, &vec![vec![0,1]; 8]) == "⢸\n⢸\n");
+}
+
+#[test]
+fn dither() {
+	// 4x4 cells cover the whole dither pattern once
+	let grid = |c| vec![vec![c; 4]; 4];
+	assert!(dots(&braille(3, &grid(0))) == 0);
+	assert!(dots(&braille(3, &grid(1))) == 8);
+	assert!(dots(&braille(3, &grid(2))) == 16);
+
+	assert!(dots(&braille(5, &grid(1))) == 4);
+	assert!(dots(&braille(5, &grid(3))) == 12);
+}
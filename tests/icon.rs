@@ -0,0 +1,55 @@
+use ca1d::{automate_with, Border, CAEvalType, HtmlWriter, Pattern, RuleIcon, WriterOpts, CA};
+
+#[test]
+fn rule30_icon() {
+	let ca = CA::new(3, 2, CAEvalType::new("rule=30").unwrap(), Border::Ring);
+	let icon = RuleIcon::new(&ca).unwrap();
+
+	let outs: Vec<u8> = icon.entries.iter().map(|(_, o)| *o).collect();
+	assert!(outs == vec![0,0,0,1,1,1,1,0]);
+	assert!(icon.entries[0].0 == Pattern::Cells(vec![1,1,1]));
+	assert!(icon.entries[6].0 == Pattern::Cells(vec![0,0,1]));
+	assert!(icon.svg(&[(0,0,0), (255,255,255)], 4).matches("<rect").count() == 8 * 4);
+}
+
+#[test]
+fn icon_rows_wrap() {
+	let ca = CA::new(3, 2, CAEvalType::new("rule=30").unwrap(), Border::Ring);
+	let rows = RuleIcon::new(&ca).unwrap().rows(4);
+
+	// 2 is the background: entries 3 wide, a cell apart, 2 rows of 4
+	assert!(rows.len() == 5);
+	assert!(rows[0] == vec![1,1,1,2,1,1,0,2,1,0,1,2,1,0,0]);
+	assert!(rows[1] == vec![2,0,2,2,2,0,2,2,2,0,2,2,2,1,2]);
+	assert!(rows[2].iter().all(|c| *c == 2));
+	assert!(rows[4] == vec![2,1,2,2,2,1,2,2,2,1,2,2,2,0,2]);
+}
+
+#[test]
+fn code_icon_sums() {
+	// 3 symbols, 3 neighbors: sums 0..=6, code digits low sum first
+	let ca = CA::new(3, 3, CAEvalType::new("code=1599").unwrap(), Border::Ring);
+	let icon = RuleIcon::new(&ca).unwrap();
+
+	assert!(icon.entries.len() == 7);
+	assert!(icon.entries[0].0 == Pattern::Sum(6));
+	let outs: Vec<u8> = icon.entries.iter().rev().map(|(_, o)| *o).collect();
+	assert!(outs == vec![0,2,0,2,1,0,2]);
+}
+
+#[test]
+fn html_report() {
+	let ca = CA::new(3, 2, CAEvalType::new("rule=90").unwrap(), Border::Ring);
+	let opts = WriterOpts { run: ca.run_info(), ..WriterOpts::default() };
+
+	let mut out: Vec<u8> = Vec::new();
+	let mut w = HtmlWriter::new(&mut out, 2, 5, 3, &opts).unwrap();
+	automate_with(&mut w, 0, 3, &ca, &vec![0,0,1,0,0]).unwrap();
+
+	let html = String::from_utf8(out).unwrap();
+	assert!(html.starts_with("<!DOCTYPE html>"));
+	assert!(html.contains("<svg"));
+	// the PNG signature, base64 encoded
+	assert!(html.contains("data:image/png;base64,iVBORw0KGgo"));
+	assert!(html.trim_end().ends_with("</html>"));
+}
@@ -0,0 +1,105 @@
+use ca1d::{CAWriter, GIFWriter, PNGWriter, SVGWriter, SixelWriter, WriterOpts};
+
+#[test]
+fn svg_runs_and_viewbox() {
+    let opts = WriterOpts { scale: 3, ..WriterOpts::default() };
+    let mut out: Vec<u8> = Vec::new();
+    let mut w = SVGWriter::new(&mut out, 2, 6, 2, &opts).unwrap();
+    w.write_line(&vec![1,1,1,0,0,1]).unwrap();
+    w.write_line(&vec![0,0,0,0,0,0]).unwrap();
+    w.finish().unwrap();
+
+    let text = String::from_utf8(out).unwrap();
+    assert!(text.contains(r#"width="18" height="6" viewBox="0 0 6 2""#));
+
+    // the background, then one rect per run of 1s and none for the 0s
+    let rects: Vec<&str> = text.lines().filter(|l| l.starts_with("<rect")).collect();
+    assert!(rects.len() == 3);
+    assert!(rects[1].starts_with(r#"<rect x="0" y="0" width="3" height="1""#));
+    assert!(rects[2].starts_with(r#"<rect x="5" y="0" width="1" height="1""#));
+    assert!(text.ends_with("</svg>\n"));
+}
+
+// a GIF of three generations, and how many frames and what loop count it has
+fn gif(loops: u16) -> (usize, Option<u16>) {
+    let opts = WriterOpts { loops, ..WriterOpts::default() };
+    let mut out: Vec<u8> = Vec::new();
+    let mut w = GIFWriter::new(&mut out, 2, 4, &opts).unwrap();
+    for row in [[0,1,0,0], [1,0,1,0], [0,0,0,1]] {
+        w.write_line(&row.to_vec()).unwrap();
+    }
+    w.finish().unwrap();
+    // the encoder holds on to out until it's dropped
+    drop(w);
+
+    let mut decoder = gif::DecodeOptions::new().read_info(out.as_slice()).unwrap();
+    let mut frames = 0;
+    while decoder.read_next_frame().unwrap().is_some() {
+        frames += 1;
+    }
+
+    // NETSCAPE2.0, then a sub block of 1 and the count
+    let ext = out.windows(11).position(|b| b == b"NETSCAPE2.0");
+    let count = ext.map(|i| u16::from_le_bytes([out[i + 13], out[i + 14]]));
+    (frames, count)
+}
+
+#[test]
+fn gif_frames_and_loops() {
+    // repeats after the first play, 0 is forever
+    assert!(gif(0) == (3, Some(0)));
+    assert!(gif(3) == (3, Some(2)));
+    // once needs no extension at all
+    assert!(gif(1) == (3, None));
+}
+
+#[test]
+fn sixel_bands() {
+    let mut out: Vec<u8> = Vec::new();
+    let mut w = SixelWriter::new(&mut out, 2, 3, 7, &WriterOpts::default()).unwrap();
+    for _ in 0..7 {
+        w.write_line(&vec![1,0,0]).unwrap();
+    }
+    w.finish().unwrap();
+
+    let text = String::from_utf8(out).unwrap();
+    // DCS with the size, then a color register per symbol
+    assert!(text.starts_with("\x1bPq\"1;1;3;7#0;2;0;0;0#1;2;100;0;0"));
+    // a full band of six rows, then the one row left over
+    assert!(text.contains("#0?~~$#1~??$-#0?@@$#1@??$-"));
+    assert!(text.ends_with("-\x1b\\"));
+}
+
+// a PNG promised 3 rows high, with lines written into it
+fn png(radix: u32, lines: usize) -> (std::io::Result<()>, Vec<u8>) {
+    let mut out: Vec<u8> = Vec::new();
+    let mut w = PNGWriter::new(&mut out, radix, 5, 3, &WriterOpts::default()).unwrap();
+    let mut result = Ok(());
+    for _ in 0..lines {
+        result = result.and_then(|_| w.write_line(&vec![0,1,0,1,(radix - 1) as u8]));
+    }
+    let result = result.and_then(|_| w.finish());
+    (result, out)
+}
+
+#[test]
+fn png_bit_depth() {
+    for (radix, depth) in [(2, png::BitDepth::One), (4, png::BitDepth::Two), (5, png::BitDepth::Four), (16, png::BitDepth::Four), (17, png::BitDepth::Eight)] {
+        let (result, out) = png(radix, 3);
+        result.unwrap();
+
+        let mut reader = png::Decoder::new(std::io::Cursor::new(out)).read_info().unwrap();
+        assert!(reader.info().bit_depth == depth);
+        assert!((reader.info().width, reader.info().height) == (5, 3));
+
+        let mut buf = vec![0; reader.output_buffer_size().unwrap()];
+        reader.next_frame(&mut buf).unwrap();
+    }
+}
+
+#[test]
+fn png_row_count() {
+    // the header's hite is a promise either way
+    assert!(png(2, 2).0.is_err());
+    assert!(png(2, 4).0.is_err());
+}
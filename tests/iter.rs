@@ -0,0 +1,42 @@
+use ca1d::{Border, CAEvalType, CA};
+
+// https://mathworld.wolfram.com/Rule90.html
+#[test]
+fn rule90_iter() {
+	let ca = CA::new(
+		3,
+		2,
+		CAEvalType::new(&String::from("rule=90")).unwrap(),
+		Border::Fixed,
+	);
+
+	let gens: Vec<_> = ca.iter(vec![0,0,0,1,0,0,0]).take(4).collect();
+
+	assert!(gens == vec![
+		vec![0,0,0,1,0,0,0],
+		vec![0,0,1,0,1,0,0],
+		vec![0,1,0,0,0,1,0],
+		vec![1,0,1,0,1,0,1],
+	]);
+
+	let third = ca.iter(vec![0,0,0,1,0,0,0]).step_by(3).nth(1).unwrap();
+	assert!(third == gens[3]);
+}
+
+#[test]
+fn stepper_matches_iter() {
+	let ca = CA::new(
+		3,
+		3,
+		CAEvalType::new(&String::from("code=777")).unwrap(),
+		Border::Ring,
+	);
+
+	let start = vec![0,0,0,1,0,0,0];
+	let mut stepper = ca.stepper(start.clone());
+
+	for config in ca.iter(start).take(10) {
+		assert!(*stepper.current() == config);
+		stepper.step();
+	}
+}
@@ -0,0 +1,49 @@
+use ca1d::{automate_with, read_config, Border, CAEvalType, PaletteSpec, PNGWriter, RawWriter, WriterOpts, CA};
+
+fn rule(r: &str, radix: u32) -> CA {
+	CA::new(3, radix, CAEvalType::new(r).unwrap(), Border::Ring)
+}
+
+#[test]
+fn text_last_row() {
+	let text = "00100\n01010\n\n1A0z0\n  \n";
+	let config = read_config(text.as_bytes(), 36, &PaletteSpec::Classic, 1).unwrap();
+	assert!(config == vec![1,10,0,35,0]);
+
+	assert!(read_config(text.as_bytes(), 2, &PaletteSpec::Classic, 1).is_err());
+	assert!(read_config("".as_bytes(), 2, &PaletteSpec::Classic, 1).is_err());
+
+	// the Raw magic is also a row of base 36 digits
+	let config = read_config("CA1D0\nca1d1\n".as_bytes(), 36, &PaletteSpec::Classic, 1).unwrap();
+	assert!(config == vec![12,10,1,13,1]);
+	assert!(read_config("CA1D\n".as_bytes(), 36, &PaletteSpec::Classic, 1).unwrap() == vec![12,10,1,13]);
+}
+
+#[test]
+fn raw_last_row() {
+	let ca = rule("rule=90", 2);
+	let opts = WriterOpts { pack: true, run: ca.run_info(), ..WriterOpts::default() };
+
+	let mut out: Vec<u8> = Vec::new();
+	let mut w = RawWriter::new(&mut out, 2, 5, 3, &opts).unwrap();
+	let (_, after) = automate_with(&mut w, 0, 3, &ca, &vec![0,0,1,0,0]).unwrap();
+
+	// after is one step past the last row written
+	let config = read_config(out.as_slice(), 2, &PaletteSpec::Classic, 1).unwrap();
+	assert!(ca.gtf(&config) == after);
+}
+
+#[test]
+fn png_last_row() {
+	let ca = rule("code=1599", 3);
+	let start = vec![0,0,0,1,2,0,0,0];
+	let opts = WriterOpts { scale: 3, grid: true, palette: PaletteSpec::Viridis, ..WriterOpts::default() };
+
+	let mut out: Vec<u8> = Vec::new();
+	let mut w = PNGWriter::new(&mut out, 3, start.len(), 4, &opts).unwrap();
+	let (_, after) = automate_with(&mut w, 0, 4, &ca, &start).unwrap();
+
+	let config = read_config(out.as_slice(), 3, &PaletteSpec::Viridis, 3).unwrap();
+	assert!(config.len() == start.len());
+	assert!(ca.gtf(&config) == after);
+}
@@ -0,0 +1,31 @@
+use ca1d::{automate_with, Border, CAEvalType, CAWriter, NpyWriter, CA};
+
+#[test]
+fn npy_header_and_cells() {
+	let ca = CA::new(
+		3,
+		2,
+		CAEvalType::new(&String::from("rule=90")).unwrap(),
+		Border::Ring,
+	);
+
+    let mut out: Vec<u8> = Vec::new();
+    let mut w = NpyWriter::new(&mut out, 5, 3).unwrap();
+	automate_with(&mut w, 0, 3, &ca, &vec![0,0,1,0,0]).unwrap();
+
+	assert!(out.starts_with(b"\x93NUMPY\x01\x00"));
+	let len = u16::from_le_bytes([out[8], out[9]]) as usize;
+	let header = std::str::from_utf8(&out[10..10 + len]).unwrap();
+	assert!((10 + len) % 64 == 0);
+	assert!(header.starts_with("{'descr': '|u1', 'fortran_order': False, 'shape': (3, 5), }"));
+	assert!(header.ends_with('\n'));
+	assert!(out[10 + len..] == [0,0,1,0,0, 0,1,0,1,0, 1,0,0,0,1]);
+}
+
+#[test]
+fn npy_short_run_fails() {
+    let mut out: Vec<u8> = Vec::new();
+    let mut w = NpyWriter::new(&mut out, 5, 3).unwrap();
+	w.write_line(&vec![0,0,1,0,0]).unwrap();
+	assert!(w.finish().is_err());
+}
@@ -0,0 +1,26 @@
+use ca1d::PaletteSpec;
+
+#[test]
+fn greyscale() {
+	let p: PaletteSpec = "greyscale".parse().unwrap();
+
+	assert!(p.colors(3).unwrap() == vec![(0,0,0), (128,128,128), (255,255,255)]);
+}
+
+#[test]
+fn gradient() {
+	let p: PaletteSpec = "gradient:000000,ff0000,ffffff".parse().unwrap();
+
+	assert!(p.colors(5).unwrap() == vec![
+		(0,0,0), (128,0,0), (255,0,0), (255,128,128), (255,255,255),
+	]);
+}
+
+#[test]
+fn list() {
+	let p: PaletteSpec = "#ffffff,000000,ff8000".parse().unwrap();
+
+	assert!(p.colors(2).unwrap() == vec![(255,255,255), (0,0,0)]);
+	assert!(p.colors(4).is_err());
+	assert!("ff80".parse::<PaletteSpec>().is_err());
+}
@@ -0,0 +1,124 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use ca1d::{ConfigPattern, Lattice};
+
+fn build(pattern: &str, width: usize) -> Result<Lattice, &'static str> {
+	let mut rng = StdRng::seed_from_u64(1);
+	ConfigPattern::new(pattern, 2)?.build(width, 2, &mut rng)
+}
+
+#[test]
+fn digits_and_groups() {
+	assert!(build("1", 5).unwrap() == vec![0,0,1,0,0]);
+	// odd leftovers go right
+	assert!(build("11", 5).unwrap() == vec![0,1,1,0,0]);
+	assert!(build("(01)*3", 8).unwrap() == vec![0,0,1,0,1,0,1,0]);
+	assert!(build("1(01)*", 6).unwrap() == vec![1,0,1,0,1,0]);
+	// digits straight after * are its count
+	assert!(build("1(0)*1", 5).unwrap() == vec![0,1,0,0,0]);
+	assert!(build("((1)*)*2", 6).is_err());
+	assert!(build("((1)*2)*2", 6).unwrap() == vec![0,1,1,1,1,0]);
+}
+
+#[test]
+fn placement_background_align() {
+	assert!(build("0:1,3:11", 6).unwrap() == vec![1,0,0,1,1,0]);
+	assert!(build("bg=01;1:00", 6).unwrap() == vec![0,0,0,1,0,1]);
+	assert!(build("align=left;11", 5).unwrap() == vec![1,1,0,0,0]);
+	assert!(build("align=right;11", 5).unwrap() == vec![0,0,0,1,1]);
+	assert!(build("1; 4:1", 5).unwrap() == vec![0,0,1,0,1]);
+}
+
+#[test]
+fn random_cells() {
+	assert!(build("@0.0", 10).unwrap() == vec![0; 10]);
+	assert!(build("@1.0", 10).unwrap() == vec![1; 10]);
+	assert!(build("@1.0*3", 7).unwrap() == vec![0,0,1,1,1,0,0]);
+	assert!(build("@", 50).unwrap().len() == 50);
+	assert!(build("@", 50) == build("@", 50));
+
+	assert!(ConfigPattern::new("@0.5*4", 2).unwrap().is_random());
+	assert!(ConfigPattern::new("(@*2)*2", 2).unwrap().is_random());
+	assert!(!ConfigPattern::new("(01)*", 2).unwrap().is_random());
+}
+
+#[test]
+fn errors() {
+	assert!(ConfigPattern::new("", 2).is_err());
+	assert!(ConfigPattern::new("012", 2).is_err());
+	assert!(ConfigPattern::new("(01", 2).is_err());
+	assert!(ConfigPattern::new("01)", 2).is_err());
+	assert!(ConfigPattern::new("@(01)*", 2).is_err());
+	assert!(ConfigPattern::new("@1.5", 2).is_err());
+	assert!(ConfigPattern::new("align=up;1", 2).is_err());
+	assert!(ConfigPattern::new("1;1", 2).is_err());
+	assert!(ConfigPattern::new("x:1", 2).is_err());
+	assert!(ConfigPattern::new("1-1", 2).is_err());
+
+	assert!(build("111", 2).is_err());
+	assert!(build("(1)*3", 2).is_err());
+	assert!(build("4:11", 5).is_err());
+}
+
+#[test]
+fn huge_counts() {
+	assert!(build("(1)*99999999999999999", 5).is_err());
+	assert!(build("((1)*99999999999)*99999999999", 5).is_err());
+	assert!(build("(1)*18446744073709551615@*18446744073709551615", 5).is_err());
+	assert!(build("(()*99999999)*99999999", 5).unwrap() == vec![0; 5]);
+	assert!(build("1(((1)*0)*99999999)*99999999", 5).unwrap() == vec![0, 0, 1, 0, 0]);
+}
+
+#[test]
+fn density_weights() {
+	let mut rng = StdRng::seed_from_u64(7);
+	let never = ConfigPattern::new("@", 3).unwrap().density(&[0.0], 3).unwrap();
+	assert!(never.build(20, 3, &mut rng).unwrap() == vec![0; 20]);
+
+	let only2 = ConfigPattern::new("@", 3).unwrap().density(&[0.0, 0.0, 1.0], 3).unwrap();
+	assert!(only2.build(20, 3, &mut rng).unwrap() == vec![2; 20]);
+
+	// an explicit @ density wins over the weights
+	let both = ConfigPattern::new("@1.0", 2).unwrap().density(&[1.0, 0.0], 2).unwrap();
+	assert!(both.build(5, 2, &mut rng).unwrap() == vec![1; 5]);
+
+	// the same seed gives the same config
+	let some = ConfigPattern::new("@", 3).unwrap().density(&[0.3], 3).unwrap();
+	let a = some.build(64, 3, &mut StdRng::seed_from_u64(9)).unwrap();
+	assert!(a == some.build(64, 3, &mut StdRng::seed_from_u64(9)).unwrap());
+	assert!(a.contains(&0) && a.iter().any(|c| *c != 0));
+
+	let p = ConfigPattern::new("@", 3).unwrap();
+	assert!(p.clone().density(&[1.5], 3).is_err());
+	assert!(p.clone().density(&[1.0, 1.0], 3).is_err());
+	assert!(p.clone().density(&[1.0, -1.0, 1.0], 3).is_err());
+	assert!(p.density(&[0.0, 0.0, 0.0], 3).is_err());
+}
+
+#[test]
+fn overflow() {
+	assert!(build("1100111", 5).is_err());
+	// centered, both ends are cut
+	assert!(build("overflow=truncate;1100111", 5).unwrap() == vec![1,0,0,1,1]);
+	assert!(build("overflow=truncate;align=left;1100111", 5).unwrap() == vec![1,1,0,0,1]);
+	assert!(build("overflow=truncate;align=right;1100111", 5).unwrap() == vec![0,0,1,1,1]);
+	assert!(build("overflow=wrap;align=left;1100111", 5).unwrap() == vec![1,1,0,0,1]);
+	assert!(build("overflow=wrap;align=left;0000011", 5).unwrap() == vec![1,1,0,0,0]);
+	assert!(build("overflow=wrap;3:111", 5).unwrap() == vec![1,0,0,1,1]);
+	assert!(build("overflow=truncate;3:111", 5).unwrap() == vec![0,0,0,1,1]);
+	assert!(build("overflow=up;1", 5).is_err());
+	assert!(build("1", 0).is_err());
+
+	let mut rng = StdRng::seed_from_u64(1);
+	let p = ConfigPattern::new("111", 2).unwrap().overflow(ca1d::Overflow::Truncate);
+	assert!(p.build(2, 2, &mut rng).unwrap() == vec![1,1]);
+}
+
+#[test]
+fn upper_case() {
+	let mut rng = StdRng::seed_from_u64(1);
+	let p = ConfigPattern::new("aZ", 36).unwrap();
+	assert!(p.build(2, 36, &mut rng).unwrap() == vec![10, 35]);
+	assert!(ConfigPattern::new("A", 10).is_err());
+}
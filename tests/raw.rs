@@ -0,0 +1,75 @@
+use ca1d::{automate_with, Border, CAEvalType, Lattice, RawReader, RawWriter, RunInfo, WriterOpts, CA};
+
+// runs rule 1599 (radix 3) through a Raw writer and reads it back
+fn roundtrip(pack: bool) {
+    let rule = CAEvalType::new("code=1599").unwrap();
+    let ca = CA::new(3, 3, rule.clone(), Border::Fixed);
+    let start: Lattice = vec![0,0,0,0,0,1,2,0,0,0,0];
+    let opts = WriterOpts { pack, run: ca.run_info(), ..WriterOpts::default() };
+
+    let mut out: Vec<u8> = Vec::new();
+    let mut w = RawWriter::new(&mut out, 3, start.len(), 6, &opts).unwrap();
+    automate_with(&mut w, 0, 6, &ca, &start).unwrap();
+
+    let reader = RawReader::new(out.as_slice()).unwrap();
+    let header = reader.header().clone();
+    assert!(header.radix == 3);
+    assert!(header.width == start.len());
+    assert!(header.generations == 6);
+    assert!(header.packed == pack);
+    assert!(header.run == RunInfo { nabor_size: 3, rule, border: Border::Fixed, ..RunInfo::default() });
+
+    let read: Vec<Lattice> = reader.map(|l| l.unwrap()).collect();
+    let expected: Vec<Lattice> = header.to_ca().iter(start).take(6).collect();
+    assert!(read == expected);
+}
+
+#[test]
+fn raw_roundtrip() {
+    roundtrip(false);
+}
+
+#[test]
+fn raw_roundtrip_packed() {
+    roundtrip(true);
+}
+
+#[test]
+fn raw_rejects_short_file() {
+    let ca = CA::new(3, 2, CAEvalType::new("rule=90").unwrap(), Border::Ring);
+    let opts = WriterOpts { run: ca.run_info(), ..WriterOpts::default() };
+
+    let mut out: Vec<u8> = Vec::new();
+    let mut w = RawWriter::new(&mut out, 2, 5, 3, &opts).unwrap();
+    automate_with(&mut w, 0, 2, &ca, &vec![0,0,1,0,0]).unwrap_err();
+
+    let rows: Vec<_> = RawReader::new(out.as_slice()).unwrap().collect();
+    assert!(rows.len() == 3);
+    assert!(rows[2].is_err());
+}
+
+// a header as RawHeader writes it, for a radix 2 rule 90 run
+fn header(packed: bool, width: u64, generations: u64) -> Vec<u8> {
+    let mut out = b"CA1D".to_vec();
+    out.extend([1, packed as u8, 2, 0]);
+    out.extend(3u32.to_le_bytes());
+    out.extend([0]);
+    out.extend(1u32.to_le_bytes());
+    out.extend([90]);
+    out.extend(width.to_le_bytes());
+    out.extend(generations.to_le_bytes());
+    out
+}
+
+#[test]
+fn raw_rejects_huge_width() {
+    // the row size overflows
+    assert!(RawReader::new(header(false, 1 << 62, 1).as_slice()).is_err());
+
+    // fits, but there's no row behind it to read
+    let mut file = header(true, 1 << 40, 1);
+    file.extend([0; 16]);
+    let rows: Vec<_> = RawReader::new(file.as_slice()).unwrap().collect();
+    assert!(rows.len() == 1);
+    assert!(rows[0].is_err());
+}
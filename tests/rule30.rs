@@ -19,7 +19,7 @@ fn rule30() {
                                15,
                                &ca,
                                &start_config,
-                               );
+                               ).unwrap();
 
 	assert!(config == vec![1,1,0,1,1,1,1,0,0,1,1,0,1,0,0,1,0,1,1,1,1,1,0,0,1,1,1,1,1,1,1]);
 }
@@ -39,7 +39,7 @@ fn rule90() {
                                1,
                                &ca,
                                &start_config,
-                               );
+                               ).unwrap();
 
 	assert!(config == vec![0,1,0,1,0]);
 }
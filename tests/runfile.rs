@@ -0,0 +1,121 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+// a scratch folder per test
+fn scratch(name: &str) -> PathBuf {
+	let dir = std::env::temp_dir().join(format!("ca1d-runfile-{}-{}", std::process::id(), name));
+	let _ = fs::remove_dir_all(&dir);
+	fs::create_dir_all(&dir).unwrap();
+	dir
+}
+
+fn ca1d(args: &[&str]) -> Output {
+	Command::new(env!("CARGO_BIN_EXE_ca1d")).args(args).output().unwrap()
+}
+
+fn stdout(out: &Output) -> String {
+	assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+	String::from_utf8(out.stdout.clone()).unwrap()
+}
+
+// the command line -v prints, which is the argv the options came to
+fn cmd_line(out: &Output) -> String {
+	let err = String::from_utf8(out.stderr.clone()).unwrap();
+	err.lines().find(|l| l.starts_with("ca1d ")).unwrap().to_string()
+}
+
+#[test]
+fn save_and_run() {
+	let dir = scratch("save");
+	let file = dir.join("exp.toml");
+	let file = file.to_str().unwrap();
+
+	let saved = ca1d(&["3", "3", "code=1599", "@;bg=0", "--density", "0.3", "--width", "16", "--to", "4", "-o", "Cell", "-v", "1", "--save", file]);
+	let run = ca1d(&["run", file, "-v", "1"]);
+
+	assert!(stdout(&saved) == stdout(&run));
+	assert!(stdout(&run).lines().count() == 4);
+	assert!(cmd_line(&saved) == cmd_line(&run));
+	assert!(cmd_line(&run).contains("--seed "));
+
+	// the file starts with the same command line
+	let text = fs::read_to_string(file).unwrap();
+	assert!(text.starts_with(&format!("# {}\n", cmd_line(&run))));
+
+	fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn config_file_next_to_run_file() {
+	let dir = scratch("relative");
+	fs::create_dir(dir.join("exp")).unwrap();
+	fs::write(dir.join("exp/start.txt"), "00100\n").unwrap();
+	fs::write(
+		dir.join("exp/run.toml"),
+		"radix = 2\nneighborhood = 3\nrule = \"rule=90\"\nconfig_file = \"start.txt\"\nto = 3\noutputs = [\"Cell\"]\n",
+	)
+	.unwrap();
+
+	// run from somewhere else
+	let out = ca1d(&["run", dir.join("exp/run.toml").to_str().unwrap()]);
+	assert!(stdout(&out) == "00100\n01010\n10001\n");
+
+	fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn unknown_keys() {
+	let dir = scratch("unknown");
+	let file = dir.join("bad.toml");
+	fs::write(&file, "radix = 2\nneighborhood = 3\nrule = \"rule=30\"\ncolour = 1\n").unwrap();
+
+	let out = ca1d(&["run", file.to_str().unwrap()]);
+	assert!(!out.status.success());
+	assert!(String::from_utf8_lossy(&out.stderr).contains("unknown field `colour`"));
+
+	fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn later_options_win() {
+	let dir = scratch("override");
+	let file = dir.join("exp.toml");
+	fs::write(
+		&file,
+		"radix = 2\nneighborhood = 3\nrule = \"rule=90\"\nstart = \"1\"\nwidth = 5\nto = 3\noutputs = [\"Cell\"]\n",
+	)
+	.unwrap();
+
+	let out = ca1d(&["run", file.to_str().unwrap(), "--to", "2", "--width", "7", "-v", "1"]);
+	assert!(stdout(&out) == "0001000\n0010100\n");
+	assert!(cmd_line(&out) == "ca1d 2 3 rule=90 1 --output Cell --width 7 --to 2");
+
+	fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn list_options_are_replaced() {
+	let dir = scratch("lists");
+	let file = dir.join("exp.toml");
+	fs::write(
+		&file,
+		"radix = 3\nneighborhood = 3\nrule = \"code=1599\"\nstart = \"@\"\nwidth = 8\nto = 2\nseed = 7\ndensity = [8.0, 1.0, 1.0]\noutputs = [\"Cell\"]\n",
+	)
+	.unwrap();
+
+	let out = ca1d(&["run", file.to_str().unwrap(), "--density", "0.5", "-o", "Json", "-v", "1"]);
+	let line = cmd_line(&out);
+	assert!(stdout(&out).lines().all(|l| l.starts_with('{')));
+	assert!(line.contains("--density 0.5 ") && !line.contains("8,1,1"));
+	assert!(line.contains("--output Json") && !line.contains("Cell"));
+
+	fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn seed_gives_the_same_start() {
+	// pinned, so saved runs keep replaying the same way
+	let out = ca1d(&["3", "3", "code=1599", "@;bg=0", "--density", "0.3", "--seed", "42", "--width", "24", "--to", "1", "-o", "Cell"]);
+	assert!(stdout(&out) == "020000011002201000000000\n");
+}
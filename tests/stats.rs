@@ -0,0 +1,56 @@
+use ca1d::{automate_with, Border, CAEvalType, CsvWriter, JsonWriter, Stats, WriterOpts, CA};
+
+#[test]
+fn density_runs_hamming() {
+	let mut stats = Stats::new(3);
+
+	let first = stats.update(&vec![0,0,1,2,2,2,0,0]);
+	assert!(first.density == vec![0.5, 0.125, 0.375]);
+	assert!(first.runs == 4);
+	assert!(first.hamming.is_none());
+
+	let second = stats.update(&vec![0,1,1,2,2,0,0,0]);
+	assert!(second.runs == 4);
+	assert!(second.hamming == Some(2));
+}
+
+#[test]
+fn csv_rows() {
+	let ca = CA::new(
+		3,
+		2,
+		CAEvalType::new(&String::from("rule=346")).unwrap(),
+		Border::Ring,
+	);
+	let opts = WriterOpts { run: ca.run_info(), ..WriterOpts::default() };
+
+    let mut out: Vec<u8> = Vec::new();
+    let mut w = CsvWriter::new(&mut out, 2, 4, &opts).unwrap();
+	automate_with(&mut w, 0, 2, &ca, &vec![0,1,0,0]).unwrap();
+
+	// 346 and 90 are the same radix 2, 3 neighbor rule
+	let out = String::from_utf8(out).unwrap();
+	assert!(out.contains("# rule: rule=90\n"));
+	assert!(out.ends_with("generation,cells,runs,hamming,density_0,density_1\n0,0100,3,,0.75,0.25\n1,1010,4,3,0.5,0.5\n"));
+}
+
+#[test]
+fn json_lines() {
+	let ca = CA::new(
+		3,
+		2,
+		CAEvalType::new(&String::from("rule=90")).unwrap(),
+		Border::Fixed,
+	);
+	let opts = WriterOpts { run: ca.run_info(), ..WriterOpts::default() };
+
+    let mut out: Vec<u8> = Vec::new();
+    let mut w = JsonWriter::new(&mut out, 2, 4, 2, &opts).unwrap();
+	automate_with(&mut w, 0, 2, &ca, &vec![0,1,0,0]).unwrap();
+
+	let out = String::from_utf8(out).unwrap();
+	let lines: Vec<&str> = out.lines().collect();
+	assert!(lines.len() == 3);
+	assert!(lines[0] == r#"{"cmd_line": "", "seed": null, "radix": 2, "nabor_size": 3, "rule": "rule=90", "border": "fixed", "width": 4, "generations": 2}"#);
+	assert!(lines[2] == r#"{"generation": 1, "cells": [1, 0, 1, 0], "density": [0.5, 0.5], "runs": 4, "hamming": 3}"#);
+}
@@ -0,0 +1,244 @@
+use std::cell::RefCell;
+use std::io;
+use std::path::PathBuf;
+use std::process::Command;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use ca1d::{automate_with, follow_with, get_writer, AnsiGreyWriter, Border, CAEvalType, CAWriter, CellWriter, ColorDepth, Lattice, Output, OutputSpec, TeeWriter, TransposeWriter, UnicodeAnsiWriter, WriterOpts, CA};
+
+// collects generations instead of printing them
+struct Collect {
+    lines: Vec<Lattice>,
+    finished: bool,
+}
+
+impl CAWriter for Collect {
+    fn write_line(&mut self, v: &Lattice) -> io::Result<()> {
+        self.lines.push(v.clone());
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.finished = true;
+        Ok(())
+    }
+}
+
+#[test]
+fn custom_writer() {
+    let start_config = vec![0,0,1,0,0];
+	let ca = CA::new(
+		3,
+		2,
+		CAEvalType::new(&String::from("rule=90")).unwrap(),
+		Border::Ring,
+	);
+
+    let mut w = Collect { lines: Vec::new(), finished: false };
+	let (_, config) = automate_with(&mut w, 0, 2, &ca, &start_config).unwrap();
+
+	assert!(w.finished);
+	assert!(w.lines == vec![vec![0,0,1,0,0], vec![0,1,0,1,0]]);
+	assert!(config == vec![1,0,0,0,1]);
+}
+
+#[test]
+fn cell_writer_to_vec() {
+    let start_config = vec![0,0,1,0,0];
+	let ca = CA::new(
+		3,
+		2,
+		CAEvalType::new(&String::from("rule=90")).unwrap(),
+		Border::Ring,
+	);
+
+    let mut out: Vec<u8> = Vec::new();
+    let mut w = CellWriter::new(&mut out, start_config.len());
+	automate_with(&mut w, 0, 3, &ca, &start_config).unwrap();
+
+	assert!(out == b"00100\n01010\n10001\n");
+}
+
+// stands in for ^C after a few lines
+struct StopAfter<'a> {
+    lines: usize,
+    stop: &'a AtomicBool,
+}
+
+impl CAWriter for StopAfter<'_> {
+    fn write_line(&mut self, _: &Lattice) -> io::Result<()> {
+        self.lines += 1;
+        if self.lines == 3 {
+            self.stop.store(true, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn follow_until_stopped() {
+    let start_config = vec![0,0,1,0,0];
+	let ca = CA::new(
+		3,
+		2,
+		CAEvalType::new(&String::from("rule=90")).unwrap(),
+		Border::Ring,
+	);
+
+    let stop = AtomicBool::new(false);
+    let mut w = StopAfter { lines: 0, stop: &stop };
+	let (_, config) = follow_with(&mut w, 0, Duration::ZERO, &ca, &start_config, &stop).unwrap();
+
+	assert!(w.lines == 3);
+	assert!(config == vec![1,1,0,1,1]);
+}
+
+// a buffer get_writer can own that the test can still read
+#[derive(Clone, Default)]
+struct Shared(Rc<RefCell<Vec<u8>>>);
+
+impl io::Write for Shared {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		self.0.borrow_mut().extend_from_slice(buf);
+		Ok(buf.len())
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		Ok(())
+	}
+}
+
+#[test]
+fn ansi_grey_piped_any_radix() {
+	let opts = WriterOpts { color: ColorDepth::NoColor, ..WriterOpts::default() };
+	let ca = CA::new(3, 8, CAEvalType::new("rule=12345").unwrap(), Border::Ring);
+
+	let out = Shared::default();
+	let mut w = get_writer(Output::AnsiGrey, out.clone(), 8, 5, 3, &opts).unwrap();
+	automate_with(w.as_mut(), 0, 3, &ca, &vec![0,0,7,0,0]).unwrap();
+
+	let text = String::from_utf8(out.0.take()).unwrap();
+	assert!(text.lines().count() == 3);
+	assert!(text.starts_with("00700\n"));
+
+	// few enough symbols still get the shaded blocks
+	let out = Shared::default();
+	let mut w = get_writer(Output::AnsiGrey, out.clone(), 2, 3, 1, &opts).unwrap();
+	w.write_line(&vec![0,1,0]).unwrap();
+	w.finish().unwrap();
+	assert!(out.0.take() == " █ \n".as_bytes());
+}
+
+#[test]
+fn ansi16_bright_colors() {
+	let mut out: Vec<u8> = Vec::new();
+	let mut w = AnsiGreyWriter::new(&mut out, 3, ColorDepth::Ansi16);
+	w.write_line(&vec![0,1,2]).unwrap();
+	w.finish().unwrap();
+
+	// the middle grey is bright black, and no 256 color codes
+	let text = String::from_utf8(out).unwrap();
+	assert!(text.contains("\x1b[100m"));
+	assert!(!text.contains("5;"));
+
+	let mut out: Vec<u8> = Vec::new();
+	let mut w = UnicodeAnsiWriter::new(&mut out, &[(255,0,0), (0,0,0)], ColorDepth::Ansi16);
+	w.write_line(&vec![0]).unwrap();
+	w.write_line(&vec![1]).unwrap();
+	w.finish().unwrap();
+
+	let text = String::from_utf8(out).unwrap();
+	assert!(text.contains("\x1b[40m\x1b[91m"));
+	assert!(!text.contains("5;"));
+}
+
+#[test]
+fn output_specs() {
+	let spec = |s: &str| s.parse::<OutputSpec>();
+
+	assert!(spec("PNG").unwrap() == OutputSpec { output: Output::PNG, path: None });
+	assert!(spec("PNG:run.png").unwrap() == OutputSpec { output: Output::PNG, path: Some(PathBuf::from("run.png")) });
+	assert!(spec("Cell:-").unwrap().is_stdout());
+	assert!(spec("Cell:").is_err());
+	assert!(spec("Nope:run.png").is_err());
+	assert!(spec("PNG:run.png").unwrap().to_string() == "PNG:run.png");
+}
+
+#[test]
+fn one_output_on_stdout() {
+	let out = Command::new(env!("CARGO_BIN_EXE_ca1d"))
+		.args(["2", "3", "rule=30", "1", "--to", "2", "-o", "Cell", "-o", "Json:-"])
+		.output()
+		.unwrap();
+	assert!(String::from_utf8_lossy(&out.stderr).contains("only one output can go to stdout"));
+}
+
+// a reader that went away
+struct Closed;
+
+impl io::Write for Closed {
+	fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+		Err(io::ErrorKind::BrokenPipe.into())
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		Ok(())
+	}
+}
+
+#[test]
+fn tee_drops_closed_writers() {
+	let (a, b) = (Shared::default(), Shared::default());
+	let mut tee = TeeWriter::new(vec![
+		Box::new(CellWriter::new(a.clone(), 3)),
+		Box::new(CellWriter::new(Closed, 3)),
+		Box::new(CellWriter::new(b.clone(), 3)),
+	]);
+
+	tee.write_line(&vec![0,1,0]).unwrap();
+	tee.write_line(&vec![1,0,1]).unwrap();
+	tee.finish().unwrap();
+	assert!(*a.0.borrow() == b"010\n101\n");
+	assert!(*b.0.borrow() == b"010\n101\n");
+
+	// and it gives up once nobody is left
+	let mut tee = TeeWriter::new(vec![Box::new(CellWriter::new(Closed, 3))]);
+	assert!(tee.write_line(&vec![0,1,0]).unwrap_err().kind() == io::ErrorKind::BrokenPipe);
+}
+
+#[test]
+fn transpose_cell_order() {
+	let out = Shared::default();
+	// the inner writer is as wide as the run is long
+	let mut w = TransposeWriter::new(Box::new(CellWriter::new(out.clone(), 2)), 3, 2);
+	w.write_line(&vec![0,1,2]).unwrap();
+	w.write_line(&vec![2,0,1]).unwrap();
+	w.finish().unwrap();
+	assert!(out.0.take() == b"02\n10\n21\n");
+}
+
+#[test]
+fn scaled_grid_transposed_png() {
+	let opts = WriterOpts { scale: 3, grid: true, transpose: true, ..WriterOpts::default() };
+	let out = Shared::default();
+	// 16 symbols plus the grid color keeps pixels a byte each
+	let mut w = get_writer(Output::PNG, out.clone(), 16, 4, 2, &opts).unwrap();
+	w.write_line(&vec![1,2,3,4]).unwrap();
+	w.write_line(&vec![5,6,7,8]).unwrap();
+	w.finish().unwrap();
+
+	let mut reader = png::Decoder::new(io::Cursor::new(out.0.take())).read_info().unwrap();
+	let mut pixels = vec![0; reader.output_buffer_size().unwrap()];
+	reader.next_frame(&mut pixels).unwrap();
+
+	// 2 generations across and 4 cells down, 3 pixels a cell
+	let (width, hite) = (reader.info().width as usize, reader.info().height as usize);
+	assert!((width, hite) == (6, 12));
+
+	// the first pixel of each cell, then the grid on each block's last row and column
+	let cell = |x: usize, y: usize| pixels[y * 3 * width + x * 3];
+	assert!((0..4).map(|y| (cell(0, y), cell(1, y))).collect::<Vec<_>>() == [(1, 5), (2, 6), (3, 7), (4, 8)]);
+	assert!(pixels[2] == 16 && pixels[2 * width] == 16);
+}